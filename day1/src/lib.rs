@@ -1,5 +1,6 @@
 use std::error::Error;
 use std::fs;
+use std::num::ParseIntError;
 
 pub struct Config {
     pub file_path: String,
@@ -36,6 +37,15 @@ pub fn string_to_num_vec(content: String) -> Vec<u32> {
         .collect()
 }
 
+/// Like `string_to_num_vec`, but reports malformed lines instead of panicking.
+pub fn try_string_to_num_vec(content: &str) -> Result<Vec<u32>, ParseIntError> {
+    content
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| line.trim().parse::<u32>())
+        .collect()
+}
+
 pub fn number_of_greater_values(numbers: Vec<u32>) -> usize {
     // adapted solution from u/u/-WorstWizard
     numbers
@@ -65,6 +75,12 @@ mod tests {
         assert_eq!(7, number_of_greater_values(content));
     }
 
+    #[test]
+    fn parse_depths() {
+        assert_eq!(Ok(vec![199, 200, 208]), try_string_to_num_vec("199\r\n200\r\n208\r\n"));
+        assert!(try_string_to_num_vec("199\ndeep\n").is_err());
+    }
+
     #[test]
     fn sum_three_values() {
         let numbers = vec![2,3,4,5,6];
@@ -0,0 +1,247 @@
+//! Many submarines flying their own scripts in lockstep, one command per tick.
+//!
+//! A fleet file lists one submarine per line as `<id> <script>`, with script paths
+//! relative to the fleet file:
+//!
+//! ```text
+//! # id    script
+//! alpha   alpha.sub
+//! bravo   scripts/bravo.sub
+//! ```
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::thread;
+
+use crate::script::{self, Command, Loader, ScriptError};
+use crate::{Position, SteeringModel, Sub, SurfacePolicy};
+
+pub struct Member {
+    pub id: String,
+    pub commands: Vec<Command>,
+}
+
+pub struct Fleet {
+    pub members: Vec<Member>,
+}
+
+/// Two or more submarines at the same place after the same tick.
+#[derive(Debug, PartialEq)]
+pub struct Collision {
+    pub tick: usize,
+    pub pos: Position,
+    pub ids: Vec<String>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Outcome {
+    pub id: String,
+    pub pos: Position,
+    pub product: Option<i64>,
+    /// Set when a command failed; the submarine stays where it was from then on.
+    pub error: Option<ScriptError>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct FleetReport {
+    pub ticks: usize,
+    pub outcomes: Vec<Outcome>,
+    pub collisions: Vec<Collision>,
+}
+
+impl Fleet {
+    pub fn parse(file: &str, source: &str, loader: &dyn Loader) -> Result<Fleet, ScriptError> {
+        let error = |line: usize, message: String| ScriptError { file: file.to_string(), line, message };
+        let mut members: Vec<Member> = vec![];
+        for (i, line) in source.lines().enumerate() {
+            let line_no = i + 1;
+            let line = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+            let (id, path) = match line.split_once(char::is_whitespace) {
+                Some((id, path)) => (id, path.trim()),
+                None => return Err(error(line_no, format!("expected '<id> <script>', found '{}'", line))),
+            };
+            if members.iter().any(|m| m.id == id) {
+                return Err(error(line_no, format!("submarine '{}' is listed twice", id)));
+            }
+            let path = match Path::new(file).parent() {
+                Some(dir) => dir.join(path).to_string_lossy().into_owned(),
+                None => path.to_string(),
+            };
+            let source = loader
+                .load(&path)
+                .map_err(|e| error(line_no, format!("cannot read script {}: {}", path, e)))?;
+            let commands = script::parse(&path, &source, loader)?.commands()?;
+            members.push(Member { id: id.to_string(), commands });
+        }
+        Ok(Fleet { members })
+    }
+
+    /// Runs every submarine, spreading the work over `threads` threads.
+    pub fn simulate(&self, model: &dyn SteeringModel, surface: SurfacePolicy, threads: usize) -> FleetReport {
+        let threads = threads.max(1);
+        let chunk = self.members.len().div_ceil(threads).max(1);
+        let tracks: Vec<Track> = thread::scope(|s| {
+            let handles: Vec<_> = self
+                .members
+                .chunks(chunk)
+                .map(|members| s.spawn(move || members.iter().map(|m| fly(m, model, surface)).collect::<Vec<_>>()))
+                .collect();
+            handles.into_iter().flat_map(|h| h.join().unwrap()).collect()
+        });
+
+        let ticks = tracks.iter().map(|t| t.positions.len()).max().unwrap_or(0);
+        let tick_chunk = ticks.div_ceil(threads).max(1);
+        let collisions: Vec<(usize, Position, Vec<usize>)> = thread::scope(|s| {
+            let tracks = &tracks;
+            let handles: Vec<_> = (1..=ticks)
+                .step_by(tick_chunk)
+                .map(|start| {
+                    let end = (start + tick_chunk).min(ticks + 1);
+                    s.spawn(move || (start..end).flat_map(|tick| collisions_at(tick, tracks)).collect::<Vec<_>>())
+                })
+                .collect();
+            handles.into_iter().flat_map(|h| h.join().unwrap()).collect()
+        });
+
+        let outcomes = self
+            .members
+            .iter()
+            .zip(tracks)
+            .map(|(member, track)| {
+                let pos = track.position(ticks);
+                Outcome { id: member.id.clone(), pos, product: pos.x.checked_mul(pos.y), error: track.error }
+            })
+            .collect();
+        let collisions = collisions
+            .into_iter()
+            .map(|(tick, pos, members)| Collision {
+                tick,
+                pos,
+                ids: members.into_iter().map(|i| self.members[i].id.clone()).collect(),
+            })
+            .collect();
+        FleetReport { ticks, outcomes, collisions }
+    }
+}
+
+/// Where one submarine is after each of its commands.
+struct Track {
+    positions: Vec<Position>,
+    error: Option<ScriptError>,
+}
+
+impl Track {
+    /// Position after `tick`; submarines that ran out of commands stay put.
+    fn position(&self, tick: usize) -> Position {
+        match tick {
+            0 => Position::default(),
+            _ => self.positions.get(tick - 1).or(self.positions.last()).copied().unwrap_or_default(),
+        }
+    }
+}
+
+fn fly(member: &Member, model: &dyn SteeringModel, surface: SurfacePolicy) -> Track {
+    let mut sub = Sub::with_policy(surface);
+    let mut positions = Vec::with_capacity(member.commands.len());
+    for command in &member.commands {
+        if let Err(e) = sub.mv(model, &command.dir) {
+            let error = ScriptError { file: command.file.clone(), line: command.line, message: e.to_string() };
+            return Track { positions, error: Some(error) };
+        }
+        positions.push(sub.pos);
+    }
+    Track { positions, error: None }
+}
+
+/// Groups of submarines sharing a position after `tick`. Submarines that have all
+/// stopped moving are not reported again for staying together.
+fn collisions_at(tick: usize, tracks: &[Track]) -> Vec<(usize, Position, Vec<usize>)> {
+    let mut at: HashMap<Position, Vec<usize>> = HashMap::new();
+    for (i, track) in tracks.iter().enumerate() {
+        at.entry(track.position(tick)).or_default().push(i);
+    }
+    let mut found: Vec<_> = at
+        .into_iter()
+        .filter(|(_, members)| members.len() > 1 && members.iter().any(|&i| tracks[i].positions.len() >= tick))
+        .map(|(pos, members)| (tick, pos, members))
+        .collect();
+    found.sort_by_key(|(_, _, members)| members[0]);
+    found
+}
+
+impl FleetReport {
+    pub fn deepest(&self) -> Option<&Outcome> {
+        self.outcomes.iter().max_by_key(|o| o.pos.y)
+    }
+
+    pub fn furthest(&self) -> Option<&Outcome> {
+        self.outcomes.iter().max_by_key(|o| o.pos.x)
+    }
+
+    pub fn largest_product(&self) -> Option<&Outcome> {
+        self.outcomes.iter().filter(|o| o.product.is_some()).max_by_key(|o| o.product)
+    }
+
+    pub fn smallest_product(&self) -> Option<&Outcome> {
+        self.outcomes.iter().filter(|o| o.product.is_some()).min_by_key(|o| o.product)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Aim;
+
+    fn fleet() -> Fleet {
+        let mut files = HashMap::new();
+        files.insert("subs/a.sub".to_string(), "forward 5\ndown 5\nforward 8\nup 3\ndown 8\nforward 2".to_string());
+        files.insert("subs/b.sub".to_string(), "forward 5\nforward 1\ndown 1".to_string());
+        files.insert("subs/c.sub".to_string(), "down 1\nforward 5\nup 10\nforward 1".to_string());
+        let source = "# three subs\nalpha a.sub\nbravo  b.sub\n\ncharlie c.sub # fails at tick 4\n";
+        Fleet::parse("subs/fleet.txt", source, &files).unwrap()
+    }
+
+    #[test]
+    fn parses_fleet_file() {
+        let fleet = fleet();
+        let ids: Vec<&str> = fleet.members.iter().map(|m| m.id.as_str()).collect();
+        assert_eq!(vec!["alpha", "bravo", "charlie"], ids);
+        assert_eq!(3, fleet.members[1].commands.len());
+
+        let files: HashMap<String, String> = HashMap::new();
+        let err = Fleet::parse("fleet.txt", "\nalpha", &files).err().unwrap();
+        assert_eq!("fleet.txt:2: expected '<id> <script>', found 'alpha'", err.to_string());
+    }
+
+    #[test]
+    fn lockstep_simulation() {
+        let report = fleet().simulate(&Aim, SurfacePolicy::Error, 1);
+        assert_eq!(6, report.ticks);
+        assert_eq!(Position { x: 15, y: 60 }, report.outcomes[0].pos);
+        assert_eq!(Some(900), report.outcomes[0].product);
+        assert_eq!(Position { x: 6, y: 0 }, report.outcomes[1].pos);
+        assert_eq!(Position { x: 5, y: 5 }, report.outcomes[2].pos);
+        assert_eq!(4, report.outcomes[2].error.as_ref().unwrap().line);
+
+        // alpha and bravo fly the same first leg
+        assert_eq!(Collision { tick: 1, pos: Position { x: 5, y: 0 }, ids: vec!["alpha".to_string(), "bravo".to_string()] },
+                   report.collisions[0]);
+        assert_eq!(1, report.collisions.len());
+
+        assert_eq!("alpha", report.deepest().unwrap().id);
+        assert_eq!("alpha", report.furthest().unwrap().id);
+        assert_eq!("bravo", report.smallest_product().unwrap().id);
+    }
+
+    #[test]
+    fn threads_do_not_change_the_result() {
+        let fleet = fleet();
+        let sequential = fleet.simulate(&Aim, SurfacePolicy::Error, 1);
+        for threads in [2, 3, 8] {
+            assert_eq!(sequential, fleet.simulate(&Aim, SurfacePolicy::Error, threads));
+        }
+    }
+}
@@ -1,111 +1,600 @@
 use std::error::Error;
+use std::fmt::Formatter;
 use std::fs;
+use std::thread;
+
+pub mod fleet;
+pub mod planner;
+pub mod script;
+pub mod seabed;
+pub mod trajectory;
+
+use trajectory::{Sample, Trajectory};
 
 pub struct Config {
-    pub file_path: String,
+    pub task: Task,
+    pub model: Option<String>,
+    pub surface: SurfacePolicy,
+    pub trajectory: Option<String>,
+    pub seabed: Option<String>,
 }
 
 impl Config {
     pub fn build(
         mut args: impl Iterator<Item=String>,
-    ) -> Result<Config, &'static str> {
+    ) -> Result<Config, String> {
         args.next();
 
-        let file_path = match args.next() {
-            Some(arg) => arg,
-            None => return Err("Didn't get a file path"),
+        let mut task = match args.next().as_deref() {
+            Some("plan") => {
+                let mut coordinate = || args.next().and_then(|arg| arg.parse::<i64>().ok());
+                match (coordinate(), coordinate()) {
+                    (Some(x), Some(y)) => Task::Plan(planner::Mission {
+                        target: Position { x, y },
+                        limits: planner::Limits::default(),
+                    }),
+                    _ => return Err("Expected a horizontal position and a depth after plan".to_string()),
+                }
+            }
+            Some("fleet") => match args.next() {
+                Some(file_path) => Task::Fleet { file_path, threads: None },
+                None => return Err("Expected a fleet file after fleet".to_string()),
+            },
+            Some(arg) => Task::Simulate { file_path: arg.to_string() },
+            None => return Err("Didn't get a file path".to_string()),
         };
 
+        let mut model = None;
+        let mut surface = SurfacePolicy::default();
+        let mut trajectory = None;
+        let mut seabed = None;
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--surface" => {
+                    surface = match args.next().as_deref() {
+                        Some("clamp") => SurfacePolicy::Clamp,
+                        Some("error") => SurfacePolicy::Error,
+                        Some("allow") => SurfacePolicy::Allow,
+                        _ => return Err("Expected 'clamp', 'error' or 'allow' after --surface".to_string()),
+                    }
+                }
+                "--trajectory" | "--seabed" if !matches!(task, Task::Simulate { .. }) => {
+                    return Err(format!("{} only applies to running a script", arg));
+                }
+                "--trajectory" => match args.next() {
+                    Some(path) => trajectory = Some(path),
+                    None => return Err("Expected a file path after --trajectory".to_string()),
+                },
+                "--max-aim" | "--max-commands" => {
+                    let mission = match &mut task {
+                        Task::Plan(mission) => mission,
+                        _ => return Err("--max-aim and --max-commands only apply to plan".to_string()),
+                    };
+                    let value = args.next().and_then(|v| v.parse::<u32>().ok());
+                    match (arg.as_str(), value) {
+                        ("--max-aim", Some(value)) => mission.limits.max_aim = Some(value),
+                        (_, Some(value)) => mission.limits.max_commands = Some(value as usize),
+                        _ => return Err("Expected a number after --max-aim or --max-commands".to_string()),
+                    }
+                }
+                "--threads" => match (&mut task, args.next().and_then(|v| v.parse::<usize>().ok())) {
+                    (Task::Fleet { threads, .. }, Some(n)) if n > 0 => *threads = Some(n),
+                    (Task::Fleet { .. }, _) => return Err("Expected a positive number after --threads".to_string()),
+                    _ => return Err("--threads only applies to fleet".to_string()),
+                },
+                "--seabed" => match args.next() {
+                    Some(path) => seabed = Some(path),
+                    None => return Err("Expected a day1 depth file after --seabed".to_string()),
+                },
+                name if model_by_name(name).is_some() => match task {
+                    // the planner always steers with aim
+                    Task::Plan(_) => return Err("plan does not take a steering model".to_string()),
+                    _ => model = Some(arg),
+                },
+                flag if flag.starts_with("--") => return Err(format!("unknown option '{}'", flag)),
+                _ => return Err(format!("Unknown steering model '{}', expected 'direct' or 'aim'", arg)),
+            }
+        }
+
+        if let Task::Plan(mission) = &mut task {
+            mission.limits.surface = surface;
+        }
+
         Ok(Config {
-            file_path,
+            task,
+            model,
+            surface,
+            trajectory,
+            seabed,
         })
     }
 }
 
-struct Position {
-    x: u32,
-    y: u32,
+#[derive(Debug, PartialEq)]
+pub enum Task {
+    /// Run a pilot script and report where it ends.
+    Simulate { file_path: String },
+    /// Find the shortest script that reaches a target.
+    Plan(planner::Mission),
+    /// Run every submarine of a fleet file in lockstep.
+    Fleet { file_path: String, threads: Option<usize> },
+}
+
+#[derive(Debug, PartialEq, Eq, Hash, Default, Clone, Copy)]
+pub struct Position {
+    pub x: i64,
+    pub y: i64,
+}
+
+/// What happens when a command would take the submarine above the surface.
+#[derive(Debug, PartialEq, Default, Clone, Copy)]
+pub enum SurfacePolicy {
+    /// Stop at the surface, the depth never drops below 0.
+    Clamp,
+    /// Reject the command.
+    #[default]
+    Error,
+    /// Negative depths are allowed and mean "above surface".
+    Allow,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Violation {
+    Overflow,
+    AboveSurface,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct SubError {
+    pub index: usize,
+    pub command: Direction,
+    pub violation: Violation,
+}
+
+impl std::fmt::Display for SubError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let reason = match self.violation {
+            Violation::Overflow => "overflows the submarine state",
+            Violation::AboveSurface => "takes the submarine above the surface",
+        };
+        write!(f, "command {} ({}) {}", self.index, self.command, reason)
+    }
 }
 
-struct Sub {
-    pos: Position,
-    aim: u32,
+impl Error for SubError {}
+
+#[derive(Debug, Default)]
+pub struct Sub {
+    pub pos: Position,
+    pub aim: i64,
+    pub surface: SurfacePolicy,
+    pub trajectory: Option<Trajectory>,
+    steps: usize,
 }
 
 impl Sub {
-    fn mv(&mut self, dir: Direction) {
-        match dir {
-            Direction::Up(scalar) => self.aim -= scalar,
-            Direction::Down(scalar) => self.aim += scalar,
+    pub fn new() -> Sub {
+        Sub::default()
+    }
+
+    pub fn with_policy(surface: SurfacePolicy) -> Sub {
+        Sub { surface, ..Sub::default() }
+    }
+
+    /// Records the state after every command from now on.
+    pub fn recording(mut self) -> Sub {
+        self.trajectory = Some(Trajectory::new());
+        self
+    }
+
+    /// Number of commands executed so far, which is also the index of the next one.
+    pub fn steps(&self) -> usize {
+        self.steps
+    }
+
+    /// Executes one command. On error the submarine state is left untouched.
+    pub fn mv(&mut self, model: &dyn SteeringModel, dir: &Direction) -> Result<(), SubError> {
+        let (pos, aim) = (self.pos, self.aim);
+        let result = model.steer(self, dir);
+        if let Err(violation) = result {
+            self.pos = pos;
+            self.aim = aim;
+            return Err(SubError { index: self.steps, command: *dir, violation });
+        }
+        if let Some(trajectory) = &mut self.trajectory {
+            trajectory.push(Sample { index: self.steps, command: *dir, pos: self.pos, aim: self.aim });
+        }
+        self.steps += 1;
+        Ok(())
+    }
+
+    pub fn run(&mut self, model: &dyn SteeringModel, commands: &[Direction]) -> Result<(), SubError> {
+        for dir in commands {
+            self.mv(model, dir)?;
+        }
+        Ok(())
+    }
+
+    pub fn advance(&mut self, dx: i64) -> Result<(), Violation> {
+        self.pos.x = self.pos.x.checked_add(dx).ok_or(Violation::Overflow)?;
+        Ok(())
+    }
+
+    pub fn dive(&mut self, dy: i64) -> Result<(), Violation> {
+        let y = self.pos.y.checked_add(dy).ok_or(Violation::Overflow)?;
+        self.pos.y = match self.surface {
+            _ if y >= 0 => y,
+            SurfacePolicy::Clamp => 0,
+            SurfacePolicy::Error => return Err(Violation::AboveSurface),
+            SurfacePolicy::Allow => y,
+        };
+        Ok(())
+    }
+
+    pub fn turn(&mut self, da: i64) -> Result<(), Violation> {
+        self.aim = self.aim.checked_add(da).ok_or(Violation::Overflow)?;
+        Ok(())
+    }
+
+    pub fn get(&self) -> Option<i64> {
+        self.pos.x.checked_mul(self.pos.y)
+    }
+}
+
+/// How a submarine interprets the `up`, `down`, `forward` and `back` commands.
+/// Models are shared between the threads of a fleet simulation.
+pub trait SteeringModel: Sync {
+    fn name(&self) -> &'static str;
+    fn steer(&self, sub: &mut Sub, dir: &Direction) -> Result<(), Violation>;
+}
+
+/// Part 1: `up` and `down` change the depth directly.
+pub struct Direct;
+
+impl SteeringModel for Direct {
+    fn name(&self) -> &'static str {
+        "direct"
+    }
+
+    fn steer(&self, sub: &mut Sub, dir: &Direction) -> Result<(), Violation> {
+        match *dir {
+            Direction::Up(scalar) => sub.dive(-i64::from(scalar)),
+            Direction::Down(scalar) => sub.dive(i64::from(scalar)),
+            Direction::Forward(scalar) => sub.advance(i64::from(scalar)),
+            Direction::Back(scalar) => sub.advance(-i64::from(scalar)),
+        }
+    }
+}
+
+/// Part 2: `up` and `down` change the aim, `forward` dives along it and `back` reverses that.
+pub struct Aim;
+
+impl SteeringModel for Aim {
+    fn name(&self) -> &'static str {
+        "aim"
+    }
+
+    fn steer(&self, sub: &mut Sub, dir: &Direction) -> Result<(), Violation> {
+        match *dir {
+            Direction::Up(scalar) => sub.turn(-i64::from(scalar)),
+            Direction::Down(scalar) => sub.turn(i64::from(scalar)),
             Direction::Forward(scalar) => {
-                self.pos.x += scalar;
-                self.pos.y += scalar * self.aim;
+                let scalar = i64::from(scalar);
+                sub.advance(scalar)?;
+                sub.dive(scalar.checked_mul(sub.aim).ok_or(Violation::Overflow)?)
+            }
+            Direction::Back(scalar) => {
+                let scalar = i64::from(scalar);
+                sub.advance(-scalar)?;
+                sub.dive(scalar.checked_mul(sub.aim).and_then(i64::checked_neg).ok_or(Violation::Overflow)?)
             }
         }
     }
 }
 
-enum Direction
+pub fn model_by_name(name: &str) -> Option<Box<dyn SteeringModel>> {
+    match name {
+        "direct" => Some(Box::new(Direct)),
+        "aim" => Some(Box::new(Aim)),
+        _ => None,
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Direction
 {
     Up(u32),
     Down(u32),
-    Forward(u32)
+    Forward(u32),
+    Back(u32),
 }
 
-impl Direction {
-    fn from(s: &str) -> Direction {
-        let s: Vec<&str> = s.split_ascii_whitespace().collect();
-        let scalar = s[1].parse::<u32>().unwrap();
-        match s[0] {
-            "down" => Direction::Down(scalar),
-            "forward" => Direction::Forward(scalar),
-            "up" => Direction::Up(scalar),
-            _ => panic!("ahh")
+impl std::fmt::Display for Direction {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Direction::Up(scalar) => write!(f, "up {}", scalar),
+            Direction::Down(scalar) => write!(f, "down {}", scalar),
+            Direction::Forward(scalar) => write!(f, "forward {}", scalar),
+            Direction::Back(scalar) => write!(f, "back {}", scalar),
         }
     }
 }
 
+/// Parses a script that does not include other files.
+pub fn parse(content: &str) -> Result<Vec<Direction>, script::ScriptError> {
+    let commands = script::parse("<input>", content, &script::NoIncludes)?.commands()?;
+    Ok(commands.into_iter().map(|c| c.dir).collect())
+}
 
+pub fn simulate(
+    model: &dyn SteeringModel,
+    commands: &[Direction],
+    surface: SurfacePolicy,
+) -> Result<Sub, SubError> {
+    let mut sub = Sub::with_policy(surface);
+    sub.run(model, commands)?;
+    Ok(sub)
+}
 
 pub fn run(config: Config) -> Result<(), Box<dyn Error>> {
-    let mut sub = Sub {
-        pos: Position {
-            x: 0,
-            y: 0
-        },
-        aim: 0
+    let file_path = match &config.task {
+        Task::Simulate { file_path } => file_path,
+        Task::Plan(mission) => {
+            let script = planner::plan(mission)?;
+            planner::verify(mission, &script)?;
+            script.iter().for_each(|dir| println!("{}", dir));
+            return Ok(());
+        }
+        Task::Fleet { file_path, threads } => return run_fleet(&config, file_path, *threads),
+    };
+    let content = fs::read_to_string(file_path)?;
+    let commands = script::parse(file_path, &content, &script::FileLoader)?.commands()?;
+
+    let seabed = match &config.seabed {
+        Some(path) => Some(seabed::Seabed::from_sonar(&fs::read_to_string(path)?)?),
+        None => None,
     };
 
-    fs::read_to_string(config.file_path)
-        .expect("Can't read input.")
-        .lines()
-        .for_each(|s| sub.mv(Direction::from(s)));
+    let models: Vec<Box<dyn SteeringModel>> = match config.model {
+        Some(name) => model_by_name(&name).into_iter().collect(),
+        None => vec![Box::new(Direct), Box::new(Aim)],
+    };
+
+    let several = models.len() > 1;
+    for model in models {
+        let mut sub = Sub::with_policy(config.surface);
+        if config.trajectory.is_some() || seabed.is_some() {
+            sub = sub.recording();
+        }
+        script::execute(&mut sub, model.as_ref(), &commands)?;
+        match sub.get() {
+            Some(answer) => println!("Answer ({}) {}", model.name(), answer),
+            None => println!("Answer ({}) overflows, x={} depth={}", model.name(), sub.pos.x, sub.pos.y),
+        }
+        if let (Some(path), Some(trajectory)) = (&config.trajectory, &sub.trajectory) {
+            let path = if several { tagged_path(path, model.name()) } else { path.clone() };
+            fs::write(&path, export(trajectory, &path)?)?;
+            println!(
+                "Trajectory ({}) written to {}: max depth {}, distance {:.1}",
+                model.name(), path, trajectory.max_depth(), trajectory.total_distance()
+            );
+        }
+        if let (Some(seabed), Some(trajectory)) = (&seabed, &sub.trajectory) {
+            report_survey(model.name(), &seabed.survey(trajectory), &commands);
+        }
+    }
+    Ok(())
+}
+
+fn run_fleet(config: &Config, file_path: &str, threads: Option<usize>) -> Result<(), Box<dyn Error>> {
+    let fleet = fleet::Fleet::parse(file_path, &fs::read_to_string(file_path)?, &script::FileLoader)?;
+    let threads = threads.unwrap_or_else(|| thread::available_parallelism().map_or(1, |n| n.get()));
+    let models: Vec<Box<dyn SteeringModel>> = match &config.model {
+        Some(name) => model_by_name(name).into_iter().collect(),
+        None => vec![Box::new(Direct), Box::new(Aim)],
+    };
 
-    println!("Answer {}", sub.pos.x * sub.pos.y);
+    for model in models {
+        let report = fleet.simulate(model.as_ref(), config.surface, threads);
+        for outcome in &report.outcomes {
+            match (&outcome.error, outcome.product) {
+                (Some(e), _) => println!("{} ({}) stopped at x={} depth={}: {}", outcome.id, model.name(), outcome.pos.x, outcome.pos.y, e),
+                (None, Some(product)) => println!("{} ({}) {}", outcome.id, model.name(), product),
+                (None, None) => println!("{} ({}) overflows, x={} depth={}", outcome.id, model.name(), outcome.pos.x, outcome.pos.y),
+            }
+        }
+        for collision in &report.collisions {
+            println!(
+                "Collision ({}) at tick {}: {} at x={} depth={}",
+                model.name(), collision.tick, collision.ids.join(", "), collision.pos.x, collision.pos.y
+            );
+        }
+        if let (Some(deepest), Some(furthest)) = (report.deepest(), report.furthest()) {
+            println!(
+                "Fleet ({}) after {} ticks: deepest {} ({}), furthest {} ({})",
+                model.name(), report.ticks, deepest.id, deepest.pos.y, furthest.id, furthest.pos.x
+            );
+        }
+        if let (Some(largest), Some(smallest)) = (report.largest_product(), report.smallest_product()) {
+            println!(
+                "Fleet ({}) products: largest {} ({}), smallest {} ({})",
+                model.name(), largest.id, largest.product.unwrap_or_default(),
+                smallest.id, smallest.product.unwrap_or_default()
+            );
+        }
+    }
     Ok(())
 }
 
+fn report_survey(model: &str, survey: &seabed::Survey, commands: &[script::Command]) {
+    match survey.first_contact {
+        Some(contact) => {
+            let command = &commands[contact.index];
+            println!(
+                "Seabed ({}) hit by command {} ({}:{} {}) at x={} depth={} seabed={}",
+                model, contact.index, command.file, command.line, command.dir,
+                contact.x, contact.depth, contact.seabed
+            );
+        }
+        None => println!("Seabed ({}) never touched", model),
+    }
+    if let (Some(min), Some(mean)) = (survey.min_clearance, survey.mean_clearance) {
+        println!(
+            "Seabed ({}) min clearance {} at x={} (command {}), mean clearance {:.1} over {} points, {} points unsurveyed",
+            model, min.clearance, min.x, min.index, mean, survey.checked, survey.unsurveyed
+        );
+    }
+}
+
+/// Picks the export format from the file extension.
+fn export(trajectory: &Trajectory, path: &str) -> Result<String, String> {
+    match path.rsplit_once('.').map(|(_, ext)| ext) {
+        Some("csv") => Ok(trajectory.to_csv()),
+        Some("json") => Ok(trajectory.to_json()),
+        Some("svg") => Ok(trajectory.to_svg()),
+        _ => Err(format!("Unknown trajectory format for {}, expected .csv, .json or .svg", path)),
+    }
+}
+
+/// `dive.svg` becomes `dive-aim.svg` so both models can be exported side by side.
+fn tagged_path(path: &str, tag: &str) -> String {
+    match path.rsplit_once('.') {
+        Some((stem, ext)) => format!("{}-{}.{}", stem, tag, ext),
+        None => format!("{}-{}", path, tag),
+    }
+}
+
 
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    #[test]
-    fn parse_string_with_numbers() {
-        // numbers from website https://adventofcode.com/2021/day/2
-        let content= "forward 5
+    const COURSE: &str = "forward 5
         down 5
         forward 8
         up 3
         down 8
-        forward 2".to_string();
-        assert_eq!(vec![("forward".to_string(), 5 as u32),
-                        ("down".to_string(), 5 as u32),
-                        ("forward".to_string(), 8 as u32),
-                        ("up".to_string(), 3 as u32),
-                        ("down".to_string(), 8 as u32),
-                        ("forward".to_string(), 2 as u32)],
-                   parse(content));
-    }
-}
\ No newline at end of file
+        forward 2";
+
+    #[test]
+    fn parse_string_with_numbers() {
+        // numbers from website https://adventofcode.com/2021/day/2
+        assert_eq!(vec![Direction::Forward(5),
+                        Direction::Down(5),
+                        Direction::Forward(8),
+                        Direction::Up(3),
+                        Direction::Down(8),
+                        Direction::Forward(2)],
+                   parse(COURSE).unwrap());
+    }
+
+    #[test]
+    fn direct_model() {
+        let sub = simulate(&Direct, &parse(COURSE).unwrap(), SurfacePolicy::Error).unwrap();
+        assert_eq!(Position { x: 15, y: 10 }, sub.pos);
+        assert_eq!(Some(150), sub.get());
+    }
+
+    #[test]
+    fn aim_model() {
+        let sub = simulate(&Aim, &parse(COURSE).unwrap(), SurfacePolicy::Error).unwrap();
+        assert_eq!(Position { x: 15, y: 60 }, sub.pos);
+        assert_eq!(Some(900), sub.get());
+    }
+
+    #[test]
+    fn back_reverses_forward() {
+        let course = parse("down 2\nforward 3\nback 3").unwrap();
+        let sub = simulate(&Aim, &course, SurfacePolicy::Error).unwrap();
+        assert_eq!(Position { x: 0, y: 0 }, sub.pos);
+        let sub = simulate(&Direct, &course, SurfacePolicy::Error).unwrap();
+        assert_eq!(Position { x: 0, y: 2 }, sub.pos);
+    }
+
+    #[test]
+    fn negative_aim_does_not_underflow() {
+        let sub = simulate(&Aim, &parse("up 3\ndown 1").unwrap(), SurfacePolicy::Error).unwrap();
+        assert_eq!(-2, sub.aim);
+    }
+
+    #[test]
+    fn surface_policies() {
+        let course = parse("down 2\nup 5\nforward 1").unwrap();
+        let err = simulate(&Direct, &course, SurfacePolicy::Error).unwrap_err();
+        assert_eq!(SubError { index: 1, command: Direction::Up(5), violation: Violation::AboveSurface }, err);
+        assert_eq!("command 1 (up 5) takes the submarine above the surface", err.to_string());
+
+        let sub = simulate(&Direct, &course, SurfacePolicy::Clamp).unwrap();
+        assert_eq!(Position { x: 1, y: 0 }, sub.pos);
+
+        let sub = simulate(&Direct, &course, SurfacePolicy::Allow).unwrap();
+        assert_eq!(Position { x: 1, y: -3 }, sub.pos);
+    }
+
+    #[test]
+    fn overflow_names_command() {
+        let course = parse("forward 1\ndown 4294967295\nforward 4294967295").unwrap();
+        let err = simulate(&Aim, &course, SurfacePolicy::Error).unwrap_err();
+        assert_eq!(2, err.index);
+        assert_eq!(Violation::Overflow, err.violation);
+    }
+
+    #[test]
+    fn backing_up_overflows_when_negated() {
+        // back 2^31 at an aim of -2^32 dives by -i64::MIN
+        let course = parse("up 4294967295\nup 1\nback 2147483648").unwrap();
+        let err = simulate(&Aim, &course, SurfacePolicy::Allow).unwrap_err();
+        assert_eq!(SubError { index: 2, command: Direction::Back(2147483648), violation: Violation::Overflow }, err);
+    }
+
+    #[test]
+    fn export_paths() {
+        assert_eq!("out/dive-aim.svg", tagged_path("out/dive.svg", "aim"));
+        assert!(export(&Trajectory::new(), "dive.csv").is_ok());
+        assert!(export(&Trajectory::new(), "dive.png").is_err());
+    }
+
+    #[test]
+    fn config_selects_model() {
+        let args = ["day2", "data.txt", "aim"].iter().map(|s| s.to_string());
+        let config = Config::build(args).unwrap();
+        assert_eq!(Some("aim".to_string()), config.model);
+        assert_eq!(SurfacePolicy::Error, config.surface);
+        let args = ["day2", "data.txt", "--surface", "allow"].iter().map(|s| s.to_string());
+        assert_eq!(SurfacePolicy::Allow, Config::build(args).unwrap().surface);
+        let args = ["day2", "data.txt", "sideways"].iter().map(|s| s.to_string());
+        assert!(Config::build(args).is_err());
+    }
+
+    #[test]
+    fn config_plan() {
+        let args = ["day2", "plan", "15", "60", "--max-aim", "5"].iter().map(|s| s.to_string());
+        let config = Config::build(args).unwrap();
+        assert_eq!(Task::Plan(planner::Mission {
+            target: Position { x: 15, y: 60 },
+            limits: planner::Limits { max_aim: Some(5), ..planner::Limits::default() },
+        }), config.task);
+        let args = ["day2", "data.txt", "--max-aim", "5"].iter().map(|s| s.to_string());
+        assert!(Config::build(args).is_err());
+    }
+
+    #[test]
+    fn config_fleet() {
+        let args = ["day2", "fleet", "fleet.txt", "--threads", "4", "aim"].iter().map(|s| s.to_string());
+        assert_eq!(Task::Fleet { file_path: "fleet.txt".to_string(), threads: Some(4) }, Config::build(args).unwrap().task);
+        let args = ["day2", "data.txt", "--threads", "4"].iter().map(|s| s.to_string());
+        assert!(Config::build(args).is_err());
+        let args = ["day2", "fleet", "fleet.txt", "--seabed", "depths.txt"].iter().map(|s| s.to_string());
+        assert_eq!(Some("--seabed only applies to running a script".to_string()), Config::build(args).err());
+    }
+
+    #[test]
+    fn config_unknown_options() {
+        let build = |args: &[&str]| Config::build(["day2"].iter().chain(args).map(|s| s.to_string())).err();
+        assert_eq!(Some("unknown option '--seabd'".to_string()), build(&["data.txt", "--seabd", "depths.txt"]));
+        assert_eq!(Some("Unknown steering model 'sideways', expected 'direct' or 'aim'".to_string()), build(&["data.txt", "sideways"]));
+        assert_eq!(Some("--trajectory only applies to running a script".to_string()), build(&["plan", "1", "2", "--trajectory", "out.csv"]));
+        assert_eq!(Some("plan does not take a steering model".to_string()), build(&["plan", "1", "2", "aim"]));
+    }
+}
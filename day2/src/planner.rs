@@ -0,0 +1,281 @@
+//! Inverse of `Sub::mv`: find the shortest aim-based command script that ends at a target.
+//!
+//! Under the aim model the script is a series of `forward` legs, each flown at a
+//! constant aim, with `up`/`down` commands changing the aim in between. With a
+//! horizontal distance `x` and a depth `d` (`0 <= d <= max_aim * x`) there are only
+//! four shapes worth trying, from shortest to longest:
+//!
+//! 1. `forward x` when `d == 0`
+//! 2. `down d/x`, `forward x` when `x` divides `d`
+//! 3. `forward x-f`, `down d/f`, `forward f` for a divisor `f < x` of `d`
+//! 4. `down q`, `forward x-r`, `down 1`, `forward r` with `d = q*x + r`
+//!
+//! The last one always works, so no script needs more than four commands.
+//! Negative depths are planned the same way with `up` instead of `down`.
+
+use std::error::Error;
+use std::fmt::Formatter;
+
+use crate::{Aim, Direction, Position, Sub, SubError, SurfacePolicy};
+
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub struct Limits {
+    /// Largest absolute aim allowed at any time.
+    pub max_aim: Option<u32>,
+    pub max_commands: Option<usize>,
+    pub surface: SurfacePolicy,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Mission {
+    pub target: Position,
+    pub limits: Limits,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum PlanError {
+    /// Only `forward` moves horizontally, so the target must be ahead of the start.
+    Behind,
+    /// The target is further than a single command can express.
+    OutOfRange,
+    /// Even flying at the largest aim the whole way does not reach the depth.
+    TooDeep { max_depth: i128 },
+    AboveSurface,
+    TooManyCommands { needed: usize, limit: usize },
+}
+
+impl std::fmt::Display for PlanError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PlanError::Behind => write!(f, "the target can only be reached moving forward"),
+            PlanError::OutOfRange => write!(f, "the target is out of range"),
+            PlanError::TooDeep { max_depth } => {
+                write!(f, "the target is deeper than the reachable {} with this aim limit", max_depth)
+            }
+            PlanError::AboveSurface => write!(f, "the target is above the surface, which the surface policy forbids"),
+            PlanError::TooManyCommands { needed, limit } => {
+                write!(f, "the shortest script needs {} commands, more than the limit of {}", needed, limit)
+            }
+        }
+    }
+}
+
+impl Error for PlanError {}
+
+pub fn plan(mission: &Mission) -> Result<Vec<Direction>, PlanError> {
+    let Mission { target, limits } = *mission;
+    if target.y < 0 && limits.surface != SurfacePolicy::Allow {
+        return Err(PlanError::AboveSurface);
+    }
+    let x = u32::try_from(target.x).map_err(|_| {
+        if target.x < 0 { PlanError::Behind } else { PlanError::OutOfRange }
+    })?;
+    let depth = target.y.unsigned_abs();
+    let max_aim = limits.max_aim.unwrap_or(u32::MAX);
+
+    let script = if x == 0 {
+        if depth != 0 {
+            return Err(PlanError::Behind);
+        }
+        vec![]
+    } else {
+        let max_depth = i128::from(max_aim) * i128::from(x);
+        if i128::from(depth) > max_depth {
+            return Err(PlanError::TooDeep { max_depth });
+        }
+        shortest(x, depth, max_aim)
+    };
+
+    let script = if target.y < 0 { mirror(script) } else { script };
+    match limits.max_commands {
+        Some(limit) if script.len() > limit => Err(PlanError::TooManyCommands { needed: script.len(), limit }),
+        _ => Ok(script),
+    }
+}
+
+/// Requires `x > 0` and `depth <= max_aim * x`, which makes every aim below fit in a `u32`.
+fn shortest(x: u32, depth: u64, max_aim: u32) -> Vec<Direction> {
+    let x64 = u64::from(x);
+    if depth == 0 {
+        return vec![Direction::Forward(x)];
+    }
+    if depth.is_multiple_of(x64) {
+        return vec![Direction::Down((depth / x64) as u32), Direction::Forward(x)];
+    }
+    if let Some(leg) = divisor_leg(x64, depth, u64::from(max_aim)) {
+        return vec![
+            Direction::Forward(x - leg as u32),
+            Direction::Down((depth / leg) as u32),
+            Direction::Forward(leg as u32),
+        ];
+    }
+    let (q, r) = ((depth / x64) as u32, (depth % x64) as u32);
+    vec![Direction::Down(q), Direction::Forward(x - r), Direction::Down(1), Direction::Forward(r)]
+}
+
+/// A divisor `f` of `depth` with `f < x` and `depth / f <= max_aim`.
+fn divisor_leg(x: u64, depth: u64, max_aim: u64) -> Option<u64> {
+    let lo = depth.div_ceil(max_aim).max(1);
+    let hi = (x - 1).min(depth);
+    if lo > hi {
+        return None;
+    }
+    // Walk whichever is shorter: the candidate range or the divisor pairs up to sqrt(depth).
+    if hi - lo <= depth.isqrt() {
+        return (lo..=hi).rev().find(|f| depth.is_multiple_of(*f));
+    }
+    (1..=depth.isqrt())
+        .filter(|i| depth.is_multiple_of(*i))
+        .flat_map(|i| [i, depth / i])
+        .filter(|f| (lo..=hi).contains(f))
+        .max()
+}
+
+fn mirror(script: Vec<Direction>) -> Vec<Direction> {
+    script
+        .into_iter()
+        .map(|dir| match dir {
+            Direction::Down(scalar) => Direction::Up(scalar),
+            Direction::Up(scalar) => Direction::Down(scalar),
+            other => other,
+        })
+        .collect()
+}
+
+#[derive(Debug, PartialEq)]
+pub enum VerifyError {
+    Sub(SubError),
+    AimLimit { index: usize, aim: i64 },
+    TooManyCommands { len: usize, limit: usize },
+    Missed { end: Position },
+}
+
+impl std::fmt::Display for VerifyError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            VerifyError::Sub(e) => write!(f, "{}", e),
+            VerifyError::AimLimit { index, aim } => write!(f, "command {} leaves the aim at {}, over the limit", index, aim),
+            VerifyError::TooManyCommands { len, limit } => write!(f, "{} commands, more than the limit of {}", len, limit),
+            VerifyError::Missed { end } => write!(f, "the script ends at x={} depth={}", end.x, end.y),
+        }
+    }
+}
+
+impl Error for VerifyError {}
+
+/// Replays `script` through a `Sub` under the aim model and checks it fulfils the mission.
+pub fn verify(mission: &Mission, script: &[Direction]) -> Result<Sub, VerifyError> {
+    let limits = mission.limits;
+    if let Some(limit) = limits.max_commands {
+        if script.len() > limit {
+            return Err(VerifyError::TooManyCommands { len: script.len(), limit });
+        }
+    }
+    let mut sub = Sub::with_policy(limits.surface);
+    for (index, dir) in script.iter().enumerate() {
+        sub.mv(&Aim, dir).map_err(VerifyError::Sub)?;
+        if let Some(max_aim) = limits.max_aim {
+            if sub.aim.unsigned_abs() > u64::from(max_aim) {
+                return Err(VerifyError::AimLimit { index, aim: sub.aim });
+            }
+        }
+    }
+    if sub.pos != mission.target {
+        return Err(VerifyError::Missed { end: sub.pos });
+    }
+    Ok(sub)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mission(x: i64, y: i64, limits: Limits) -> Mission {
+        Mission { target: Position { x, y }, limits }
+    }
+
+    fn planned_len(x: i64, y: i64, limits: Limits) -> usize {
+        let mission = mission(x, y, limits);
+        let script = plan(&mission).unwrap();
+        verify(&mission, &script).unwrap();
+        script.len()
+    }
+
+    #[test]
+    fn puzzle_target() {
+        let mission = mission(15, 60, Limits::default());
+        let script = plan(&mission).unwrap();
+        assert_eq!(vec![Direction::Down(4), Direction::Forward(15)], script);
+        assert_eq!(Some(900), verify(&mission, &script).unwrap().get());
+    }
+
+    #[test]
+    fn each_shape() {
+        assert_eq!(0, planned_len(0, 0, Limits::default()));
+        assert_eq!(1, planned_len(7, 0, Limits::default()));
+        assert_eq!(2, planned_len(7, 21, Limits::default()));
+        assert_eq!(3, planned_len(7, 5, Limits::default()));
+        assert_eq!(3, planned_len(7, 23, Limits::default()));
+        assert_eq!(4, planned_len(7, 23, Limits { max_aim: Some(5), ..Limits::default() }));
+        assert_eq!(3, planned_len(7, -5, Limits { surface: SurfacePolicy::Allow, ..Limits::default() }));
+    }
+
+    #[test]
+    fn aim_limit_changes_the_plan() {
+        let limits = Limits { max_aim: Some(3), ..Limits::default() };
+        // 10 = 1 * 10 would need aim 10, a leg of 5 at aim 2 fits the limit
+        let shallow = mission(6, 10, limits);
+        let script = plan(&shallow).unwrap();
+        assert_eq!(vec![Direction::Forward(1), Direction::Down(2), Direction::Forward(5)], script);
+        verify(&shallow, &script).unwrap();
+        assert_eq!(Err(PlanError::TooDeep { max_depth: 18 }), plan(&mission(6, 19, limits)));
+    }
+
+    #[test]
+    fn shortest_matches_exhaustive_search() {
+        // Breadth-first search over every script of up to four commands with small scalars.
+        let mut reachable = std::collections::HashMap::new();
+        let mut frontier = vec![(Vec::new(), Sub::new())];
+        for len in 0..=4 {
+            let mut next = vec![];
+            for (script, sub) in frontier {
+                reachable.entry((sub.pos.x, sub.pos.y)).or_insert(len);
+                for n in 1..=8 {
+                    for dir in [Direction::Forward(n), Direction::Down(n), Direction::Up(n)] {
+                        let mut sub2 = Sub { pos: sub.pos, aim: sub.aim, ..Sub::new() };
+                        if sub2.mv(&Aim, &dir).is_ok() && (0..=8).contains(&sub2.aim) {
+                            let mut script2: Vec<Direction> = script.clone();
+                            script2.push(dir);
+                            next.push((script2, sub2));
+                        }
+                    }
+                }
+            }
+            frontier = next;
+        }
+        for x in 1..=8 {
+            for y in 0..=8 * x {
+                let limits = Limits { max_aim: Some(8), ..Limits::default() };
+                assert_eq!(reachable[&(x, y)], planned_len(x, y, limits), "target {},{}", x, y);
+            }
+        }
+    }
+
+    #[test]
+    fn impossible_missions() {
+        assert_eq!(Err(PlanError::Behind), plan(&mission(-1, 0, Limits::default())));
+        assert_eq!(Err(PlanError::Behind), plan(&mission(0, 3, Limits::default())));
+        assert_eq!(Err(PlanError::AboveSurface), plan(&mission(3, -3, Limits::default())));
+        let limits = Limits { max_aim: Some(5), max_commands: Some(3), ..Limits::default() };
+        assert_eq!(Err(PlanError::TooManyCommands { needed: 4, limit: 3 }), plan(&mission(7, 23, limits)));
+    }
+
+    #[test]
+    fn verify_rejects_bad_scripts() {
+        let mission = mission(2, 2, Limits { max_aim: Some(1), ..Limits::default() });
+        assert_eq!(Err(VerifyError::Missed { end: Position { x: 2, y: 0 } }),
+                   verify(&mission, &[Direction::Forward(2)]).map(|_| ()));
+        assert_eq!(Err(VerifyError::AimLimit { index: 0, aim: 2 }),
+                   verify(&mission, &[Direction::Down(2), Direction::Forward(1)]).map(|_| ()));
+    }
+}
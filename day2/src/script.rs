@@ -0,0 +1,563 @@
+//! Pilot scripts: the puzzle's command list plus `back`, `#` comments, named
+//! constants, `repeat N { ... }` blocks and `include "file"`.
+//!
+//! ```text
+//! const DIVE = 5
+//! repeat 3 {
+//!     forward 2   # one leg
+//!     down DIVE
+//! }
+//! include "surface.sub"
+//! ```
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt::Formatter;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::{Direction, SteeringModel, Sub};
+
+/// A script expanding to more commands than this is rejected instead of exhausting memory.
+pub const MAX_COMMANDS: usize = 10_000_000;
+
+#[derive(Debug, PartialEq)]
+pub struct ScriptError {
+    pub file: String,
+    pub line: usize,
+    pub message: String,
+}
+
+impl std::fmt::Display for ScriptError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}: {}", self.file, self.line, self.message)
+    }
+}
+
+impl Error for ScriptError {}
+
+/// Where `include` gets its files from.
+pub trait Loader {
+    fn load(&self, path: &str) -> io::Result<String>;
+}
+
+pub struct FileLoader;
+
+impl Loader for FileLoader {
+    fn load(&self, path: &str) -> io::Result<String> {
+        fs::read_to_string(path)
+    }
+}
+
+/// For scripts that must not include anything.
+pub struct NoIncludes;
+
+impl Loader for NoIncludes {
+    fn load(&self, path: &str) -> io::Result<String> {
+        Err(io::Error::new(io::ErrorKind::Unsupported, format!("cannot include {}", path)))
+    }
+}
+
+impl Loader for HashMap<String, String> {
+    fn load(&self, path: &str) -> io::Result<String> {
+        self.get(path)
+            .cloned()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, format!("{} not found", path)))
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+enum Token {
+    Ident(String),
+    Number(u32),
+    Str(String),
+    LBrace,
+    RBrace,
+    Equals,
+    Newline,
+    Eof,
+}
+
+impl std::fmt::Display for Token {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Token::Ident(name) => write!(f, "'{}'", name),
+            Token::Number(n) => write!(f, "number {}", n),
+            Token::Str(s) => write!(f, "string \"{}\"", s),
+            Token::LBrace => write!(f, "'{{'"),
+            Token::RBrace => write!(f, "'}}'"),
+            Token::Equals => write!(f, "'='"),
+            Token::Newline => write!(f, "end of line"),
+            Token::Eof => write!(f, "end of file"),
+        }
+    }
+}
+
+struct Lexer<'a> {
+    file: &'a str,
+    chars: std::iter::Peekable<std::str::Chars<'a>>,
+    line: usize,
+}
+
+impl<'a> Lexer<'a> {
+    fn new(file: &'a str, source: &'a str) -> Lexer<'a> {
+        Lexer { file, chars: source.chars().peekable(), line: 1 }
+    }
+
+    fn error(&self, message: String) -> ScriptError {
+        ScriptError { file: self.file.to_string(), line: self.line, message }
+    }
+
+    fn tokens(mut self) -> Result<Vec<(Token, usize)>, ScriptError> {
+        let mut tokens = vec![];
+        loop {
+            let token = self.next_token()?;
+            let line = if token == Token::Newline { self.line - 1 } else { self.line };
+            let done = token == Token::Eof;
+            tokens.push((token, line));
+            if done {
+                return Ok(tokens);
+            }
+        }
+    }
+
+    fn next_token(&mut self) -> Result<Token, ScriptError> {
+        while let Some(&c) = self.chars.peek() {
+            match c {
+                '\n' => {
+                    self.chars.next();
+                    self.line += 1;
+                    return Ok(Token::Newline);
+                }
+                '#' => {
+                    while self.chars.next_if(|&c| c != '\n').is_some() {}
+                }
+                c if c.is_whitespace() => {
+                    self.chars.next();
+                }
+                '{' => {
+                    self.chars.next();
+                    return Ok(Token::LBrace);
+                }
+                '}' => {
+                    self.chars.next();
+                    return Ok(Token::RBrace);
+                }
+                '=' => {
+                    self.chars.next();
+                    return Ok(Token::Equals);
+                }
+                '"' => {
+                    self.chars.next();
+                    let mut s = String::new();
+                    loop {
+                        match self.chars.next() {
+                            Some('"') => return Ok(Token::Str(s)),
+                            Some('\n') | None => return Err(self.error("unterminated string".to_string())),
+                            Some(c) => s.push(c),
+                        }
+                    }
+                }
+                c if c.is_ascii_digit() => {
+                    let mut digits = String::new();
+                    while let Some(c) = self.chars.next_if(|c| c.is_ascii_alphanumeric()) {
+                        digits.push(c);
+                    }
+                    return digits
+                        .parse()
+                        .map(Token::Number)
+                        .map_err(|_| self.error(format!("'{}' is not a number between 0 and {}", digits, u32::MAX)));
+                }
+                c if c.is_ascii_alphabetic() || c == '_' => {
+                    let mut name = String::new();
+                    while let Some(c) = self.chars.next_if(|c| c.is_ascii_alphanumeric() || *c == '_') {
+                        name.push(c);
+                    }
+                    return Ok(Token::Ident(name));
+                }
+                c => return Err(self.error(format!("unexpected character '{}'", c))),
+            }
+        }
+        Ok(Token::Eof)
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+enum Expr {
+    Number(u32),
+    Name(String),
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum Kind {
+    Forward,
+    Back,
+    Down,
+    Up,
+}
+
+#[derive(Debug, PartialEq)]
+enum Stmt {
+    Command { kind: Kind, arg: Expr, line: usize },
+    Const { name: String, value: Expr, line: usize },
+    Repeat { count: Expr, body: Vec<Stmt>, line: usize },
+    Include { program: Program },
+}
+
+/// A parsed script with all includes resolved.
+#[derive(Debug, PartialEq)]
+pub struct Program {
+    file: String,
+    body: Vec<Stmt>,
+}
+
+struct Parser<'a> {
+    file: &'a str,
+    tokens: Vec<(Token, usize)>,
+    pos: usize,
+    loader: &'a dyn Loader,
+    stack: &'a mut Vec<String>,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> &Token {
+        &self.tokens[self.pos].0
+    }
+
+    fn line(&self) -> usize {
+        self.tokens[self.pos].1
+    }
+
+    fn advance(&mut self) -> Token {
+        let token = self.tokens[self.pos].0.clone();
+        if token != Token::Eof {
+            self.pos += 1;
+        }
+        token
+    }
+
+    fn error(&self, line: usize, message: String) -> ScriptError {
+        ScriptError { file: self.file.to_string(), line, message }
+    }
+
+    fn unexpected(&self, expected: &str) -> ScriptError {
+        self.error(self.line(), format!("expected {}, found {}", expected, self.peek()))
+    }
+
+    fn block(&mut self, nested: bool) -> Result<Vec<Stmt>, ScriptError> {
+        let mut body = vec![];
+        loop {
+            match self.peek() {
+                Token::Newline => {
+                    self.advance();
+                }
+                Token::RBrace if nested => return Ok(body),
+                Token::Eof if !nested => return Ok(body),
+                Token::Eof => return Err(self.unexpected("'}'")),
+                _ => {
+                    body.push(self.statement()?);
+                    match self.peek() {
+                        Token::Newline | Token::Eof => {}
+                        Token::RBrace if nested => {}
+                        _ => return Err(self.unexpected("end of line")),
+                    }
+                }
+            }
+        }
+    }
+
+    fn statement(&mut self) -> Result<Stmt, ScriptError> {
+        let line = self.line();
+        let keyword = match self.advance() {
+            Token::Ident(keyword) => keyword,
+            token => return Err(self.error(line, format!("expected a command, found {}", token))),
+        };
+        let kind = match keyword.as_str() {
+            "forward" => Kind::Forward,
+            "back" => Kind::Back,
+            "down" => Kind::Down,
+            "up" => Kind::Up,
+            "const" => {
+                let name = match self.advance() {
+                    Token::Ident(name) if !is_keyword(&name) => name,
+                    _ => return Err(self.error(line, "expected a constant name after 'const'".to_string())),
+                };
+                if self.advance() != Token::Equals {
+                    return Err(self.error(line, format!("expected '=' after 'const {}'", name)));
+                }
+                let value = self.expr()?;
+                return Ok(Stmt::Const { name, value, line });
+            }
+            "repeat" => {
+                let count = self.expr()?;
+                if *self.peek() != Token::LBrace {
+                    return Err(self.unexpected("'{'"));
+                }
+                self.advance();
+                let body = self.block(true)?;
+                self.advance();
+                return Ok(Stmt::Repeat { count, body, line });
+            }
+            "include" => {
+                let path = match self.advance() {
+                    Token::Str(path) => path,
+                    _ => return Err(self.error(line, "expected a quoted file name after 'include'".to_string())),
+                };
+                return self.include(&path, line);
+            }
+            other => return Err(self.error(line, format!("unknown command '{}'", other))),
+        };
+        let arg = self.expr()?;
+        Ok(Stmt::Command { kind, arg, line })
+    }
+
+    fn expr(&mut self) -> Result<Expr, ScriptError> {
+        match self.peek().clone() {
+            Token::Number(n) => {
+                self.advance();
+                Ok(Expr::Number(n))
+            }
+            Token::Ident(name) if !is_keyword(&name) => {
+                self.advance();
+                Ok(Expr::Name(name))
+            }
+            _ => Err(self.unexpected("a number or constant")),
+        }
+    }
+
+    fn include(&mut self, path: &str, line: usize) -> Result<Stmt, ScriptError> {
+        // Includes are relative to the including file.
+        let resolved = match Path::new(self.file).parent() {
+            Some(dir) => dir.join(path).to_string_lossy().into_owned(),
+            None => path.to_string(),
+        };
+        if self.stack.contains(&resolved) {
+            return Err(self.error(line, format!("include cycle through {}", resolved)));
+        }
+        let source = self
+            .loader
+            .load(&resolved)
+            .map_err(|e| self.error(line, format!("cannot include {}: {}", resolved, e)))?;
+        let program = parse_nested(&resolved, &source, self.loader, self.stack)?;
+        Ok(Stmt::Include { program })
+    }
+}
+
+fn is_keyword(name: &str) -> bool {
+    matches!(name, "forward" | "back" | "down" | "up" | "const" | "repeat" | "include")
+}
+
+/// Parses `source`, which was read from `file`, resolving includes through `loader`.
+pub fn parse(file: &str, source: &str, loader: &dyn Loader) -> Result<Program, ScriptError> {
+    parse_nested(file, source, loader, &mut vec![])
+}
+
+fn parse_nested(
+    file: &str,
+    source: &str,
+    loader: &dyn Loader,
+    stack: &mut Vec<String>,
+) -> Result<Program, ScriptError> {
+    let tokens = Lexer::new(file, source).tokens()?;
+    stack.push(file.to_string());
+    let mut parser = Parser { file, tokens, pos: 0, loader, stack };
+    let body = parser.block(false);
+    parser.stack.pop();
+    Ok(Program { file: file.to_string(), body: body? })
+}
+
+/// A single movement together with the place in the script it came from.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Command {
+    pub dir: Direction,
+    pub file: String,
+    pub line: usize,
+}
+
+struct Interpreter {
+    constants: HashMap<String, u32>,
+    commands: Vec<Command>,
+}
+
+impl Interpreter {
+    fn eval(&self, file: &str, line: usize, expr: &Expr) -> Result<u32, ScriptError> {
+        match expr {
+            Expr::Number(n) => Ok(*n),
+            Expr::Name(name) => self.constants.get(name).copied().ok_or_else(|| ScriptError {
+                file: file.to_string(),
+                line,
+                message: format!("unknown constant '{}'", name),
+            }),
+        }
+    }
+
+    fn exec(&mut self, file: &str, body: &[Stmt]) -> Result<(), ScriptError> {
+        for stmt in body {
+            match stmt {
+                Stmt::Command { kind, arg, line } => {
+                    let scalar = self.eval(file, *line, arg)?;
+                    if self.commands.len() >= MAX_COMMANDS {
+                        return Err(ScriptError {
+                            file: file.to_string(),
+                            line: *line,
+                            message: format!("script expands to more than {} commands", MAX_COMMANDS),
+                        });
+                    }
+                    let dir = match kind {
+                        Kind::Forward => Direction::Forward(scalar),
+                        Kind::Back => Direction::Back(scalar),
+                        Kind::Down => Direction::Down(scalar),
+                        Kind::Up => Direction::Up(scalar),
+                    };
+                    self.commands.push(Command { dir, file: file.to_string(), line: *line });
+                }
+                Stmt::Const { name, value, line } => {
+                    if self.constants.contains_key(name) {
+                        return Err(ScriptError {
+                            file: file.to_string(),
+                            line: *line,
+                            message: format!("constant '{}' is already defined", name),
+                        });
+                    }
+                    let value = self.eval(file, *line, value)?;
+                    self.constants.insert(name.clone(), value);
+                }
+                Stmt::Repeat { count, body, line } => {
+                    let count = self.eval(file, *line, count)? as usize;
+                    if count == 0 {
+                        continue;
+                    }
+                    // the body expands the same way every time, so the first run
+                    // tells how many commands the whole loop adds
+                    let before = self.commands.len();
+                    self.exec(file, body)?;
+                    let per_run = self.commands.len() - before;
+                    // a body without commands is only run again to report its errors
+                    let runs = if per_run == 0 { count.min(2) } else { count };
+                    if per_run.checked_mul(runs).is_none_or(|total| before + total > MAX_COMMANDS) {
+                        return Err(ScriptError {
+                            file: file.to_string(),
+                            line: *line,
+                            message: format!("script expands to more than {} commands", MAX_COMMANDS),
+                        });
+                    }
+                    for _ in 1..runs {
+                        self.exec(file, body)?;
+                    }
+                }
+                Stmt::Include { program } => self.exec(&program.file, &program.body)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Program {
+    /// Evaluates constants and unrolls `repeat` blocks into the commands to execute.
+    pub fn commands(&self) -> Result<Vec<Command>, ScriptError> {
+        let mut interpreter = Interpreter { constants: HashMap::new(), commands: vec![] };
+        interpreter.exec(&self.file, &self.body)?;
+        Ok(interpreter.commands)
+    }
+}
+
+/// Runs the commands on `sub`, reporting a failing command by its place in the script.
+pub fn execute(sub: &mut Sub, model: &dyn SteeringModel, commands: &[Command]) -> Result<(), ScriptError> {
+    for command in commands {
+        sub.mv(model, &command.dir).map_err(|e| ScriptError {
+            file: command.file.clone(),
+            line: command.line,
+            message: e.to_string(),
+        })?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Aim, SurfacePolicy};
+
+    fn directions(source: &str) -> Result<Vec<Direction>, ScriptError> {
+        let commands = parse("main.sub", source, &NoIncludes)?.commands()?;
+        Ok(commands.into_iter().map(|c| c.dir).collect())
+    }
+
+    #[test]
+    fn constants_repeat_and_comments() {
+        let source = "# dive twice
+const LEG = 2
+
+repeat 2 {
+    forward LEG # go
+    down 1
+}
+back 1";
+        assert_eq!(Ok(vec![Direction::Forward(2), Direction::Down(1),
+                           Direction::Forward(2), Direction::Down(1),
+                           Direction::Back(1)]),
+                   directions(source));
+        assert_eq!(Ok(vec![Direction::Up(1), Direction::Up(1)]), directions("repeat 2 { repeat 1 { up 1 } }"));
+    }
+
+    #[test]
+    fn commands_keep_their_line() {
+        let commands = parse("main.sub", "\n\nforward 1\nrepeat 2 {\n  up 3\n}", &NoIncludes)
+            .unwrap()
+            .commands()
+            .unwrap();
+        let lines: Vec<usize> = commands.iter().map(|c| c.line).collect();
+        assert_eq!(vec![3, 5, 5], lines);
+    }
+
+    #[test]
+    fn errors_name_the_line() {
+        let err = |source| directions(source).unwrap_err().to_string();
+        assert_eq!("main.sub:2: unknown command 'sideways'", err("forward 1\nsideways 2"));
+        assert_eq!("main.sub:3: unknown constant 'X'", err("\n\nforward X"));
+        assert_eq!("main.sub:1: expected a number or constant, found end of line", err("forward\n"));
+        assert_eq!("main.sub:2: expected '}', found end of file", err("repeat 2 {\n forward 1"));
+        assert_eq!("main.sub:1: unexpected character '-'", err("up -1"));
+        assert_eq!("main.sub:2: constant 'A' is already defined", err("const A = 1\nconst A = 2"));
+        assert_eq!("main.sub:1: expected end of line, found number 2", err("forward 1 2"));
+        assert_eq!("main.sub:1: cannot include main.sub.inc: cannot include main.sub.inc", err("include \"main.sub.inc\""));
+    }
+
+    #[test]
+    fn repeat_limits() {
+        assert_eq!(Ok(vec![]), directions("repeat 4294967295 { repeat 4294967295 { } }"));
+        let err = directions("repeat 2 {\n repeat 4294967295 { up 1 }\n}").unwrap_err();
+        assert_eq!(format!("main.sub:2: script expands to more than {} commands", MAX_COMMANDS), err.to_string());
+        let err = directions("repeat 4294967295 {\n up 1\n up 2\n}").unwrap_err();
+        assert_eq!(1, err.line);
+        assert_eq!("main.sub:2: constant 'A' is already defined", directions("repeat 5 {\n const A = 1\n}").unwrap_err().to_string());
+    }
+
+    #[test]
+    fn includes() {
+        let mut files = HashMap::new();
+        files.insert("dir/legs.sub".to_string(), "const LEG = 4\nforward LEG".to_string());
+        files.insert("dir/loop.sub".to_string(), "include \"loop.sub\"".to_string());
+
+        let program = parse("dir/main.sub", "include \"legs.sub\"\ndown LEG", &files).unwrap();
+        let commands = program.commands().unwrap();
+        assert_eq!(Command { dir: Direction::Forward(4), file: "dir/legs.sub".to_string(), line: 2 }, commands[0]);
+        assert_eq!(Command { dir: Direction::Down(4), file: "dir/main.sub".to_string(), line: 2 }, commands[1]);
+
+        let err = parse("dir/main.sub", "include \"loop.sub\"", &files).unwrap_err();
+        assert_eq!("dir/loop.sub:1: include cycle through dir/loop.sub", err.to_string());
+    }
+
+    #[test]
+    fn execute_reports_line() {
+        let commands = parse("main.sub", "down 1\nforward 2\nup 4\nforward 1", &NoIncludes)
+            .unwrap()
+            .commands()
+            .unwrap();
+        let mut sub = Sub::with_policy(SurfacePolicy::Error);
+        let err = execute(&mut sub, &Aim, &commands).unwrap_err();
+        assert_eq!(4, err.line);
+        assert_eq!("main.sub:4: command 3 (forward 1) takes the submarine above the surface", err.to_string());
+    }
+}
@@ -0,0 +1,166 @@
+use std::num::ParseIntError;
+use std::ops::Range;
+
+use crate::trajectory::Trajectory;
+use crate::Position;
+
+/// Seabed depth per horizontal step, as measured by the day 1 sonar sweep.
+#[derive(Debug, PartialEq)]
+pub struct Seabed {
+    depths: Vec<u32>,
+}
+
+/// A point where the submarine touched or went below the seabed.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Contact {
+    pub index: usize,
+    pub x: i64,
+    pub depth: i64,
+    pub seabed: i64,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Clearance {
+    pub index: usize,
+    pub x: i64,
+    pub clearance: i64,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Survey {
+    pub first_contact: Option<Contact>,
+    pub min_clearance: Option<Clearance>,
+    pub mean_clearance: Option<f64>,
+    /// Points on the path inside the surveyed range.
+    pub checked: usize,
+    /// Points on the path outside the surveyed range, which cannot be checked.
+    pub unsurveyed: usize,
+}
+
+impl Seabed {
+    pub fn new(depths: Vec<u32>) -> Seabed {
+        Seabed { depths }
+    }
+
+    /// Reads a day 1 input file, one depth per line.
+    pub fn from_sonar(content: &str) -> Result<Seabed, ParseIntError> {
+        Ok(Seabed::new(day1::try_string_to_num_vec(content)?))
+    }
+
+    pub fn depth_at(&self, x: i64) -> Option<i64> {
+        usize::try_from(x).ok().and_then(|x| self.depths.get(x)).map(|&d| i64::from(d))
+    }
+
+    /// Checks every integer horizontal position along the path, not just where commands end.
+    pub fn survey(&self, trajectory: &Trajectory) -> Survey {
+        let mut survey = Survey {
+            first_contact: None,
+            min_clearance: None,
+            mean_clearance: None,
+            checked: 0,
+            unsurveyed: 0,
+        };
+        let mut total: i128 = 0;
+        let mut prev = Position::default();
+        let surveyed = 0..self.depths.len() as i64;
+        for sample in &trajectory.samples {
+            let pos = sample.pos;
+            let (outside, points) = path(prev, pos, &surveyed);
+            survey.unsurveyed += outside;
+            for (x, depth) in points {
+                let seabed = self.depth_at(x).expect("the path is clipped to the surveyed range");
+                let clearance = seabed - depth;
+                survey.checked += 1;
+                total += i128::from(clearance);
+                if clearance <= 0 && survey.first_contact.is_none() {
+                    survey.first_contact = Some(Contact { index: sample.index, x, depth, seabed });
+                }
+                if survey.min_clearance.is_none_or(|min| clearance < min.clearance) {
+                    survey.min_clearance = Some(Clearance { index: sample.index, x, clearance });
+                }
+            }
+            prev = pos;
+        }
+        if survey.checked > 0 {
+            survey.mean_clearance = Some(total as f64 / survey.checked as f64);
+        }
+        survey
+    }
+}
+
+/// The positions a single command passes through inside `surveyed`, excluding
+/// where it started, and how many it passes through outside it. Moves are straight
+/// lines, so depth is interpolated at each horizontal step.
+fn path(from: Position, to: Position, surveyed: &Range<i64>) -> (usize, impl Iterator<Item = (i64, i64)>) {
+    let (dx, dy) = (i128::from(to.x) - i128::from(from.x), i128::from(to.y) - i128::from(from.y));
+    // a vertical move is a single step down to its end
+    let steps = if dx == 0 { i128::from(dy != 0) } else { dx.abs() };
+    let (start, end) = (i128::from(surveyed.start), i128::from(surveyed.end) - 1);
+    let x = i128::from(from.x);
+    // the steps landing inside, clipped before stepping so long moves cost nothing
+    let (first, last) = match dx.signum() {
+        0 if (start..=end).contains(&x) => (1, steps),
+        0 => (1, 0),
+        1 => ((start - x).max(1), (end - x).min(steps)),
+        _ => ((x - end).max(1), (x - start).min(steps)),
+    };
+    let inside = (last - first + 1).max(0);
+    let points = (first..=last).map(move |step| {
+        let x = x + step * dx.signum();
+        let y = i128::from(from.y) + dy * step / steps;
+        (x as i64, y as i64)
+    });
+    ((steps - inside) as usize, points)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse, Aim, Direct, SteeringModel, Sub, SurfacePolicy};
+
+    fn trajectory(model: &dyn SteeringModel, source: &str) -> Trajectory {
+        let mut sub = Sub::with_policy(SurfacePolicy::Error).recording();
+        sub.run(model, &parse(source).unwrap()).unwrap();
+        sub.trajectory.unwrap()
+    }
+
+    #[test]
+    fn reads_sonar_report() {
+        let seabed = Seabed::from_sonar("199\n200\n208\n").unwrap();
+        assert_eq!(Some(208), seabed.depth_at(2));
+        assert_eq!(None, seabed.depth_at(3));
+        assert_eq!(None, seabed.depth_at(-1));
+    }
+
+    #[test]
+    fn finds_contact_between_command_ends() {
+        let seabed = Seabed::new(vec![10, 10, 10, 3, 10, 10]);
+        let survey = seabed.survey(&trajectory(&Aim, "down 1\nforward 5"));
+        assert_eq!(Some(Contact { index: 1, x: 3, depth: 3, seabed: 3 }), survey.first_contact);
+        assert_eq!(Some(Clearance { index: 1, x: 3, clearance: 0 }), survey.min_clearance);
+        assert_eq!(5, survey.checked);
+        assert_eq!(0, survey.unsurveyed);
+    }
+
+    #[test]
+    fn clearance_statistics() {
+        let seabed = Seabed::new(vec![10, 10, 8]);
+        let survey = seabed.survey(&trajectory(&Direct, "down 2\nforward 3"));
+        assert_eq!(None, survey.first_contact);
+        assert_eq!(Some(Clearance { index: 1, x: 2, clearance: 6 }), survey.min_clearance);
+        assert_eq!(Some((8.0 + 8.0 + 6.0) / 3.0), survey.mean_clearance);
+        assert_eq!(1, survey.unsurveyed);
+    }
+
+    #[test]
+    fn long_moves_are_clipped_to_the_seabed() {
+        let seabed = Seabed::new(vec![10, 10, 10]);
+        let survey = seabed.survey(&trajectory(&Direct, "down 2\nforward 4294967295\nback 4294967295"));
+        assert_eq!(6, survey.checked);
+        assert_eq!(2 * 4294967295 - 6 + 1, survey.unsurveyed);
+        assert_eq!(Some(8.0), survey.mean_clearance);
+        // a vertical move off the seabed
+        let survey = seabed.survey(&trajectory(&Direct, "forward 5\ndown 1"));
+        assert_eq!((2, 4), (survey.checked, survey.unsurveyed));
+    }
+}
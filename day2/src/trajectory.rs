@@ -0,0 +1,160 @@
+use std::fmt::Write;
+
+use crate::{Direction, Position};
+
+/// The submarine state right after a command was executed.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Sample {
+    pub index: usize,
+    pub command: Direction,
+    pub pos: Position,
+    pub aim: i64,
+}
+
+/// Every state the submarine passed through, starting at the origin.
+#[derive(Debug, Default, PartialEq)]
+pub struct Trajectory {
+    pub samples: Vec<Sample>,
+}
+
+impl Trajectory {
+    pub fn new() -> Trajectory {
+        Trajectory::default()
+    }
+
+    pub fn push(&mut self, sample: Sample) {
+        self.samples.push(sample);
+    }
+
+    pub fn max_depth(&self) -> i64 {
+        self.samples.iter().map(|s| s.pos.y).fold(0, i64::max)
+    }
+
+    /// Index of the first command after which the depth is greater than `depth`.
+    pub fn first_deeper_than(&self, depth: i64) -> Option<usize> {
+        self.samples.iter().find(|s| s.pos.y > depth).map(|s| s.index)
+    }
+
+    /// Length of the path through all positions, measured in a straight line between them.
+    pub fn total_distance(&self) -> f64 {
+        let mut prev = Position::default();
+        let mut distance = 0.0;
+        for sample in &self.samples {
+            let dx = (sample.pos.x - prev.x) as f64;
+            let dy = (sample.pos.y - prev.y) as f64;
+            distance += dx.hypot(dy);
+            prev = sample.pos;
+        }
+        distance
+    }
+
+    pub fn to_csv(&self) -> String {
+        let mut out = "index,command,x,depth,aim\n".to_string();
+        for s in &self.samples {
+            writeln!(out, "{},{},{},{},{}", s.index, s.command, s.pos.x, s.pos.y, s.aim).unwrap();
+        }
+        out
+    }
+
+    pub fn to_json(&self) -> String {
+        let samples: Vec<String> = self
+            .samples
+            .iter()
+            .map(|s| format!(
+                "  {{\"index\": {}, \"command\": \"{}\", \"x\": {}, \"depth\": {}, \"aim\": {}}}",
+                s.index, s.command, s.pos.x, s.pos.y, s.aim
+            ))
+            .collect();
+        if samples.is_empty() {
+            return "[]\n".to_string();
+        }
+        format!("[\n{}\n]\n", samples.join(",\n"))
+    }
+
+    /// Side view of the dive: horizontal distance to the right, depth downwards.
+    pub fn to_svg(&self) -> String {
+        let (mut min_x, mut max_x, mut min_y, mut max_y) = (0, 0, 0, 0);
+        for s in &self.samples {
+            min_x = min_x.min(s.pos.x);
+            max_x = max_x.max(s.pos.x);
+            min_y = min_y.min(s.pos.y);
+            max_y = max_y.max(s.pos.y);
+        }
+        let width = (max_x - min_x).max(1);
+        let height = (max_y - min_y).max(1);
+
+        let mut points = "0,0".to_string();
+        for s in &self.samples {
+            write!(points, " {},{}", s.pos.x, s.pos.y).unwrap();
+        }
+
+        let mut out = String::new();
+        writeln!(
+            out,
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"{} {} {} {}\" \
+             width=\"800\" height=\"400\" preserveAspectRatio=\"none\">",
+            min_x, min_y, width, height
+        ).unwrap();
+        writeln!(
+            out,
+            "  <line x1=\"{}\" y1=\"0\" x2=\"{}\" y2=\"0\" stroke=\"steelblue\" \
+             vector-effect=\"non-scaling-stroke\"/>",
+            min_x, max_x.max(min_x + 1)
+        ).unwrap();
+        writeln!(
+            out,
+            "  <polyline points=\"{}\" fill=\"none\" stroke=\"black\" \
+             vector-effect=\"non-scaling-stroke\"/>",
+            points
+        ).unwrap();
+        out.push_str("</svg>\n");
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse, Aim, SurfacePolicy, Sub};
+
+    fn course() -> Trajectory {
+        let mut sub = Sub::with_policy(SurfacePolicy::Error).recording();
+        sub.run(&Aim, &parse("forward 5\ndown 5\nforward 8\nup 3\ndown 8\nforward 2").unwrap()).unwrap();
+        sub.trajectory.unwrap()
+    }
+
+    #[test]
+    fn records_every_command() {
+        let trajectory = course();
+        assert_eq!(6, trajectory.samples.len());
+        assert_eq!(Sample { index: 2, command: Direction::Forward(8), pos: Position { x: 13, y: 40 }, aim: 5 },
+                   trajectory.samples[2]);
+    }
+
+    #[test]
+    fn queries() {
+        let trajectory = course();
+        assert_eq!(60, trajectory.max_depth());
+        assert_eq!(Some(2), trajectory.first_deeper_than(0));
+        assert_eq!(Some(5), trajectory.first_deeper_than(40));
+        assert_eq!(None, trajectory.first_deeper_than(60));
+        let expected = 5.0 + (8.0f64 * 8.0 + 40.0 * 40.0).sqrt() + (2.0f64 * 2.0 + 20.0 * 20.0).sqrt();
+        assert!((trajectory.total_distance() - expected).abs() < 1e-9);
+    }
+
+    #[test]
+    fn exports() {
+        let trajectory = course();
+        let csv = trajectory.to_csv();
+        assert_eq!(Some("index,command,x,depth,aim"), csv.lines().next());
+        assert_eq!(Some("5,forward 2,15,60,10"), csv.lines().last());
+
+        let json = trajectory.to_json();
+        assert!(json.starts_with("[\n  {\"index\": 0, \"command\": \"forward 5\", \"x\": 5, \"depth\": 0, \"aim\": 0},"));
+        assert_eq!("[]\n", Trajectory::new().to_json());
+
+        let svg = trajectory.to_svg();
+        assert!(svg.contains("viewBox=\"0 0 15 60\""));
+        assert!(svg.contains("points=\"0,0 5,0 5,0 13,40 13,40 13,40 15,60\""));
+    }
+}
@@ -0,0 +1,27 @@
+//! Compares `PowerConsumption::from` with the bit-sliced counting engine.
+//! Run with `cargo bench --bench column_counts`.
+
+use std::thread;
+
+use day3::{bitslice, DiagnosticReport, PowerConsumption};
+
+#[path = "../src/testing.rs"]
+mod testing;
+
+use testing::{random_rows, time};
+
+fn main() {
+    let mut state = 0x9e37_79b9_7f4a_7c15;
+    let threads = thread::available_parallelism().map_or(1, |n| n.get());
+    println!("{:>10} {:>5} {:>12} {:>12} {:>12}", "rows", "bits", "per bit", "bit-sliced", format!("x{} threads", threads));
+    for (rows, width) in [(100_000, 12), (1_000_000, 32), (10_000_000, 12), (20_000_000, 64), (10_000_000, 128)] {
+        let report = DiagnosticReport::from_rows(width, random_rows(&mut state, width, rows)).unwrap();
+
+        let (naive, expected) = time(1, || PowerConsumption::from(&report));
+        let (sliced, single) = time(3, || bitslice::power_consumption(&report, 1));
+        let (parallel, actual) = time(3, || bitslice::power_consumption(&report, threads));
+        assert_eq!(expected, single);
+        assert_eq!(expected, actual);
+        println!("{:>10} {:>5} {:>12?} {:>12?} {:>12?}", rows, width, naive, sliced, parallel);
+    }
+}
@@ -0,0 +1,24 @@
+//! Compares the filtering life support search with the trie.
+//! Run with `cargo bench --bench life_support`.
+
+use day3::trie::BinaryTrie;
+use day3::{DiagnosticReport, LifeSupportRating};
+
+#[path = "../src/testing.rs"]
+mod testing;
+
+use testing::{random_rows, time};
+
+fn main() {
+    let mut state = 0x9e37_79b9_7f4a_7c15;
+    println!("{:>9} {:>5} {:>12} {:>12} {:>12}", "rows", "bits", "filter", "trie build", "trie query");
+    for (rows, width) in [(1_000, 12), (100_000, 12), (100_000, 32), (1_000_000, 20), (1_000_000, 64)] {
+        let report = DiagnosticReport::from_rows(width, random_rows(&mut state, width, rows)).unwrap();
+
+        let (filter, expected) = time(5, || LifeSupportRating::from(&report));
+        let (build, trie) = time(5, || BinaryTrie::build(&report).unwrap());
+        let (query, actual) = time(1000, || trie.life_support_rating(report.criteria()));
+        assert_eq!(expected, actual);
+        println!("{:>9} {:>5} {:>12?} {:>12?} {:>12?}", rows, width, filter, build, query);
+    }
+}
@@ -0,0 +1,194 @@
+//! Column counting for very large reports.
+//!
+//! `DiagnosticReport::column_counts` tests one bit of one row at a time. Here each
+//! row is split into 64-bit words and the words of a column of rows are added up
+//! vertically: bit `b` of the counter planes together hold the count for bit `b`,
+//! so one `u64` operation advances 64 column counts at once. Eight rows at a time
+//! are first reduced with carry-save adders (Harley-Seal), and the report is split
+//! into chunks counted on separate threads.
+//!
+//! Unknown bits are counted the same way from the report's wildcard masks and
+//! taken out of the zeros.
+
+use std::thread;
+
+use crate::{ColumnCount, DiagnosticReport, PowerConsumption, ReportError};
+
+/// Bits in each vertical counter, so it holds up to `2^PLANES - 1` groups of eight rows.
+const PLANES: usize = 16;
+
+/// Same counts as `DiagnosticReport::column_counts`, leftmost column first.
+pub fn column_counts(report: &DiagnosticReport, threads: usize) -> Vec<ColumnCount> {
+    let rows = report.rows().len();
+    let ones = count_parallel(report.rows(), report.width(), threads);
+    let unknown = count_parallel(report.wildcards(), report.width(), threads);
+    ones.into_iter()
+        .zip(unknown)
+        .map(|(ones, unknown)| ColumnCount { ones, zeros: rows - ones - unknown })
+        .collect()
+}
+
+/// Ones per column of `rows`, leftmost column first.
+fn count_parallel(rows: &[u128], width: usize, threads: usize) -> Vec<usize> {
+    let chunk = rows.len().div_ceil(threads.max(1)).max(1);
+    thread::scope(|s| {
+        let handles: Vec<_> = rows.chunks(chunk).map(|rows| s.spawn(move || count_ones(rows, width))).collect();
+        handles.into_iter().fold(vec![0; width], |mut total, h| {
+            for (total, ones) in total.iter_mut().zip(h.join().unwrap()) {
+                *total += ones;
+            }
+            total
+        })
+    })
+}
+
+/// Same result as `PowerConsumption::from`, counted with `threads` threads.
+pub fn power_consumption(report: &DiagnosticReport, threads: usize) -> Result<PowerConsumption, ReportError> {
+    PowerConsumption::from_counts(&column_counts(report, threads), report.criteria(), report.mask())
+}
+
+/// Ones per column, leftmost column first.
+fn count_ones(rows: &[u128], width: usize) -> Vec<usize> {
+    let mut ones = vec![0; width];
+    for word in 0..width.div_ceil(64) {
+        let counts = count_word(rows, word * 64);
+        for (bit, count) in counts.into_iter().enumerate() {
+            let position = word * 64 + bit;
+            if position < width {
+                ones[width - 1 - position] = count;
+            }
+        }
+    }
+    ones
+}
+
+/// Ones per bit of the 64-bit word starting at bit `shift` of every row.
+fn count_word(rows: &[u128], shift: usize) -> [usize; 64] {
+    let mut counter = VerticalCounter::new();
+    let (mut ones, mut twos, mut fours) = (0u64, 0u64, 0u64);
+    let groups = rows.chunks_exact(8);
+    let rest = groups.remainder();
+    for group in groups {
+        let w: [u64; 8] = std::array::from_fn(|i| (group[i] >> shift) as u64);
+        let (twos_a, sum) = csa(ones, w[0], w[1]);
+        let (twos_b, sum) = csa(sum, w[2], w[3]);
+        let (fours_a, twos_ab) = csa(twos, twos_a, twos_b);
+        let (twos_a, sum) = csa(sum, w[4], w[5]);
+        let (twos_b, sum) = csa(sum, w[6], w[7]);
+        let (fours_b, twos_cd) = csa(twos_ab, twos_a, twos_b);
+        let (eights, four) = csa(fours, fours_a, fours_b);
+        (ones, twos, fours) = (sum, twos_cd, four);
+        counter.add(eights);
+    }
+
+    let mut counts = counter.finish(8);
+    for (bit, count) in counts.iter_mut().enumerate() {
+        *count += 4 * (fours >> bit & 1) as usize + 2 * (twos >> bit & 1) as usize + (ones >> bit & 1) as usize;
+        *count += rest.iter().filter(|&&row| (row >> (shift + bit)) & 1 == 1).count();
+    }
+    counts
+}
+
+/// Carry-save adder: the carry and sum bits of adding `a`, `b` and `c` bit by bit.
+fn csa(a: u64, b: u64, c: u64) -> (u64, u64) {
+    let u = a ^ b;
+    ((a & b) | (u & c), u ^ c)
+}
+
+/// 64 counters stored bit-sliced: bit `b` of `planes[j]` is bit `j` of counter `b`.
+struct VerticalCounter {
+    planes: [u64; PLANES],
+    added: usize,
+    totals: [usize; 64],
+}
+
+impl VerticalCounter {
+    fn new() -> VerticalCounter {
+        VerticalCounter { planes: [0; PLANES], added: 0, totals: [0; 64] }
+    }
+
+    /// Adds one to every counter whose bit is set in `word`.
+    fn add(&mut self, word: u64) {
+        let mut carry = word;
+        for plane in self.planes.iter_mut() {
+            if carry == 0 {
+                break;
+            }
+            let next = *plane & carry;
+            *plane ^= carry;
+            carry = next;
+        }
+        self.added += 1;
+        if self.added == (1 << PLANES) - 1 {
+            self.flush();
+        }
+    }
+
+    fn flush(&mut self) {
+        for (bit, total) in self.totals.iter_mut().enumerate() {
+            *total += (0..PLANES).map(|j| ((self.planes[j] >> bit & 1) as usize) << j).sum::<usize>();
+        }
+        self.planes = [0; PLANES];
+        self.added = 0;
+    }
+
+    /// The counts, each multiplied by the `weight` of one addition.
+    fn finish(mut self, weight: usize) -> [usize; 64] {
+        self.flush();
+        self.totals.map(|total| total * weight)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{random, random_rows};
+    use crate::{BitCriteria, TiePolicy};
+
+    #[test]
+    fn puzzle_example() {
+        let report = DiagnosticReport::parse("00100\n11110\n10110\n10111\n10101\n01111\n00111\n11100\n10000\n11001\n00010\n01010").unwrap();
+        assert_eq!(report.column_counts(), column_counts(&report, 1));
+        assert_eq!(Ok(Some(198)), power_consumption(&report, 3).map(|pc| pc.get()));
+    }
+
+    #[test]
+    fn matches_power_consumption_on_random_reports() {
+        let mut state = 0x9e37_79b9_7f4a_7c15;
+        // sizes around the groups of eight, the 64-bit words and a counter flush
+        for (width, rows) in [(1, 1), (3, 7), (5, 8), (8, 9), (12, 1001), (63, 300), (64, 64), (65, 777), (100, 5000), (128, 4096), (17, 600_000)] {
+            let report = DiagnosticReport::from_rows(width, random_rows(&mut state, width, rows)).unwrap();
+            let expected = report.column_counts();
+            for threads in [1, 2, 5] {
+                assert_eq!(expected, column_counts(&report, threads), "width {} threads {}", width, threads);
+                for tie in [TiePolicy::PreferOne, TiePolicy::PreferZero, TiePolicy::Error] {
+                    let report = report.clone().with_criteria(BitCriteria::new(tie));
+                    assert_eq!(PowerConsumption::from(&report), power_consumption(&report, threads));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn matches_power_consumption_with_wildcards_and_masks() {
+        let mut state = 7;
+        for (width, rows) in [(2, 9), (12, 1001), (70, 3000), (128, 500)] {
+            let rows_data = random_rows(&mut state, width, rows);
+            let wildcards = (0..rows).map(|_| ((random(&mut state) & random(&mut state)) as u128) << 64 | random(&mut state) as u128).collect();
+            let report = DiagnosticReport::from_rows(width, rows_data)
+                .unwrap()
+                .with_wildcards(wildcards)
+                .with_mask(((random(&mut state) as u128) << 64) | random(&mut state) as u128);
+            assert_eq!(report.column_counts(), column_counts(&report, 3));
+            assert_eq!(PowerConsumption::from(&report), power_consumption(&report, 3));
+        }
+    }
+
+    #[test]
+    fn all_ones_overflow_the_counter_planes() {
+        // every row adds to every counter, forcing flushes at the plane limit
+        let rows = vec![u128::MAX >> 60; 8 * (1 << PLANES) + 3];
+        let report = DiagnosticReport::from_rows(68, rows).unwrap();
+        assert_eq!(report.column_counts(), column_counts(&report, 2));
+    }
+}
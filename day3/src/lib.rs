@@ -1,12 +1,340 @@
 use std::error::Error;
+use std::fmt::Formatter;
 use std::fs;
+use std::thread;
 
-fn binstr_to_dec(bin: &str) -> Result<u32, Box<dyn Error>> {
-    Ok(u32::from_str_radix(bin, 2)?)
+/// Reports can be up to this many bits wide.
+pub const MAX_WIDTH: usize = 128;
+
+pub mod bitslice;
+#[cfg(test)]
+mod testing;
+pub mod trie;
+
+/// Reads the digits of a base 2, 8 or 16 number. An `x` digit stands for unknown
+/// bits, which come back set in the second value and clear in the first.
+fn str_to_dec(digits: &str, radix: u32) -> Result<(u128, u128), DigitError> {
+    let bits = radix.trailing_zeros();
+    let digit_mask = (1u128 << bits) - 1;
+    let (mut value, mut wildcards) = (0u128, 0u128);
+    for (column, c) in digits.chars().enumerate() {
+        let (digit, wild) = match c {
+            'x' => (0, digit_mask),
+            c => (c.to_digit(radix).ok_or(DigitError::NotADigit(column, c))?.into(), 0),
+        };
+        if (value | wildcards) >> (128 - bits) != 0 {
+            return Err(DigitError::Overflow);
+        }
+        value = value << bits | digit;
+        wildcards = wildcards << bits | wild;
+    }
+    Ok((value, wildcards))
+}
+
+enum DigitError {
+    NotADigit(usize, char),
+    Overflow,
+}
+
+/// All bits of a `width` bit row.
+fn full_mask(width: usize) -> u128 {
+    if width >= MAX_WIDTH { u128::MAX } else { (1 << width) - 1 }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum ReportError {
+    Empty,
+    TooWide { line: usize, width: usize },
+    Ragged { line: usize, width: usize, expected: usize },
+    InvalidDigit { line: usize, column: usize, found: char, radix: u32 },
+    /// A `0b`, `0x` or `0o` prefix with nothing after it.
+    NoDigits { line: usize },
+    /// Hex and octal values need a `width` line before them.
+    UndeclaredWidth { line: usize },
+    /// A hex or octal value has bits set beyond the declared width.
+    DoesNotFit { line: usize, width: usize },
+    /// A malformed or misplaced `width` or `mask` line.
+    Header { line: usize, problem: &'static str },
+    /// A column has as many ones as zeros and the tie policy is `TiePolicy::Error`.
+    Tie { column: usize },
+    /// Filtering for a life support rating left no rows.
+    NoCandidates { rating: &'static str, column: usize },
+    /// The trie expands every wildcard into both bits, so rows may only have a few.
+    TooManyWildcards { line: usize, count: u32 },
+}
+
+impl std::fmt::Display for ReportError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ReportError::Empty => write!(f, "the report has no lines"),
+            ReportError::TooWide { line, width } => {
+                write!(f, "line {} is {} bits wide, at most {} are supported", line, width, MAX_WIDTH)
+            }
+            ReportError::Ragged { line, width, expected } => {
+                write!(f, "line {} is {} bits wide, the lines before are {}", line, width, expected)
+            }
+            ReportError::InvalidDigit { line, column, found, radix } => {
+                let expected = match radix {
+                    2 => "0, 1 or x",
+                    8 => "an octal digit or x",
+                    _ => "a hex digit or x",
+                };
+                write!(f, "line {} has '{}' in column {}, expected {}", line, found, column, expected)
+            }
+            ReportError::NoDigits { line } => write!(f, "line {} has a prefix but no digits", line),
+            ReportError::UndeclaredWidth { line } => {
+                write!(f, "line {} is hex or octal, which needs a 'width <bits>' line first", line)
+            }
+            ReportError::DoesNotFit { line, width } => {
+                write!(f, "line {} does not fit in the declared {} bits", line, width)
+            }
+            ReportError::Header { line, problem } => write!(f, "line {}: {}", line, problem),
+            ReportError::Tie { column } => {
+                write!(f, "bit position {} has as many ones as zeros", column)
+            }
+            ReportError::NoCandidates { rating, column } => {
+                write!(f, "no rows are left for the {} rating at bit position {}", rating, column)
+            }
+            ReportError::TooManyWildcards { line, count } => {
+                write!(f, "line {} has {} wildcard bits, the trie handles at most {}", line, count, trie::MAX_WILDCARDS)
+            }
+        }
+    }
+}
+
+impl Error for ReportError {}
+
+/// What to do when a column has as many ones as zeros.
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub enum TiePolicy {
+    /// Treat 1 as the most common bit, as the puzzle does.
+    #[default]
+    PreferOne,
+    PreferZero,
+    Error,
+}
+
+/// Decides the most and least common bit of a column.
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub struct BitCriteria {
+    pub tie: TiePolicy,
+}
+
+impl BitCriteria {
+    pub fn new(tie: TiePolicy) -> BitCriteria {
+        BitCriteria { tie }
+    }
+
+    /// Whether 1 is the most common bit. The least common bit is always the other
+    /// one, so on a tie `PreferOne` keeps ones for oxygen and zeros for CO2.
+    pub fn most_common(&self, count: ColumnCount, column: usize) -> Result<bool, ReportError> {
+        if !count.is_tie() {
+            return Ok(count.ones > count.zeros);
+        }
+        match self.tie {
+            TiePolicy::PreferOne => Ok(true),
+            TiePolicy::PreferZero => Ok(false),
+            TiePolicy::Error => Err(ReportError::Tie { column }),
+        }
+    }
+}
+
+/// A validated diagnostic report: every row has the same width.
+#[derive(Debug, PartialEq, Clone)]
+pub struct DiagnosticReport {
+    width: usize,
+    rows: Vec<u128>,
+    /// Unknown bits per row, empty when no row has any.
+    wildcards: Vec<u128>,
+    /// Columns taking part in the ratings.
+    mask: u128,
+    criteria: BitCriteria,
+}
+
+impl DiagnosticReport {
+    /// Reads one number per line: plain binary, or binary, hex or octal with a `0b`,
+    /// `0x` or `0o` prefix. `x` marks unknown digits. LF and CRLF line endings, blank
+    /// lines and whitespace around the numbers are all accepted.
+    ///
+    /// Binary lines set the width; hex and octal need it declared first with a
+    /// `width <bits>` line. A binary row starting with `0x` or `0o` needs the `0b`
+    /// prefix, so `0x1` with its middle bit unknown is written `0b0x1`. A `mask <value>`
+    /// line before the numbers leaves the columns where the mask is 0 out of every
+    /// rating:
+    ///
+    /// ```text
+    /// width 12
+    /// mask 0b111111110000
+    /// 0x1f3
+    /// 0o7x15
+    /// ```
+    pub fn parse(s: &str) -> Result<DiagnosticReport, ReportError> {
+        let mut width = None;
+        // whether hex and octal have a width
+        let mut declared = false;
+        let mut mask = None;
+        let mut rows = vec![];
+        let mut wildcards = vec![];
+        for (i, line) in s.lines().enumerate() {
+            let line_no = i + 1;
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            let header = |problem| ReportError::Header { line: line_no, problem };
+            if let Some(bits) = line.strip_prefix("width") {
+                if !rows.is_empty() || width.is_some() {
+                    return Err(header("the width has to be declared once, before the numbers"));
+                }
+                match bits.trim().parse() {
+                    Ok(bits) if (1..=MAX_WIDTH).contains(&bits) => (width, declared) = (Some(bits), true),
+                    _ => return Err(header("expected 'width <bits>' with 1 to 128 bits")),
+                }
+            } else if let Some(value) = line.strip_prefix("mask") {
+                if !rows.is_empty() || mask.is_some() {
+                    return Err(header("the mask has to be given once, before the numbers"));
+                }
+                match parse_value(value.trim(), line_no, &mut width, declared)? {
+                    (value, 0) => mask = Some(value),
+                    _ => return Err(header("the mask cannot have unknown bits")),
+                }
+            } else {
+                let (row, wild) = parse_value(line, line_no, &mut width, declared)?;
+                rows.push(row);
+                wildcards.push(wild);
+            }
+        }
+        match width {
+            Some(width) if !rows.is_empty() => {
+                let report = DiagnosticReport::from_rows(width, rows)?.with_wildcards(wildcards);
+                Ok(report.with_mask(mask.unwrap_or(u128::MAX)))
+            }
+            _ => Err(ReportError::Empty),
+        }
+    }
+
+    /// Uses `criteria` for all ratings from now on.
+    pub fn with_criteria(mut self, criteria: BitCriteria) -> DiagnosticReport {
+        self.criteria = criteria;
+        self
+    }
+
+    pub fn criteria(&self) -> BitCriteria {
+        self.criteria
+    }
+
+    /// Leaves the columns where `mask` is 0 out of every rating.
+    pub fn with_mask(mut self, mask: u128) -> DiagnosticReport {
+        self.mask = mask & full_mask(self.width);
+        self
+    }
+
+    pub fn mask(&self) -> u128 {
+        self.mask
+    }
+
+    /// Whether the column, counted from the left, takes part in the ratings.
+    pub fn includes(&self, column: usize) -> bool {
+        self.mask & self.column_bit(column) != 0
+    }
+
+    /// Marks unknown bits, one mask per row. They count as neither 1 nor 0, and
+    /// the life support filters keep them whichever bit they look for.
+    ///
+    /// Panics if there is not one mask per row.
+    pub fn with_wildcards(mut self, wildcards: Vec<u128>) -> DiagnosticReport {
+        assert_eq!(self.rows.len(), wildcards.len(), "one wildcard mask per row");
+        let full = full_mask(self.width);
+        if wildcards.iter().all(|&wild| wild & full == 0) {
+            self.wildcards = vec![];
+            return self;
+        }
+        for (row, wild) in self.rows.iter_mut().zip(&wildcards) {
+            *row &= !wild;
+        }
+        self.wildcards = wildcards.into_iter().map(|wild| wild & full).collect();
+        self
+    }
+
+    /// Unknown bits per row, empty when the report has none.
+    pub fn wildcards(&self) -> &[u128] {
+        &self.wildcards
+    }
+
+    fn wildcard(&self, index: usize) -> u128 {
+        self.wildcards.get(index).copied().unwrap_or(0)
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Builds a report from numbers that are already parsed.
+    pub fn from_rows(width: usize, rows: Vec<u128>) -> Result<DiagnosticReport, ReportError> {
+        if rows.is_empty() {
+            return Err(ReportError::Empty);
+        }
+        if width > MAX_WIDTH {
+            return Err(ReportError::TooWide { line: 1, width });
+        }
+        if let Some(i) = rows.iter().position(|&row| row & !full_mask(width) != 0) {
+            let found = (128 - rows[i].leading_zeros()) as usize;
+            return Err(ReportError::Ragged { line: i + 1, width: found, expected: width });
+        }
+        Ok(DiagnosticReport { width, rows, wildcards: vec![], mask: full_mask(width), criteria: BitCriteria::default() })
+    }
+
+    pub fn rows(&self) -> &[u128] {
+        &self.rows
+    }
+
+    /// The bit of a row holding `column`, counting columns from the left.
+    fn column_bit(&self, column: usize) -> u128 {
+        1 << (self.width - 1 - column)
+    }
+}
+
+/// Parses one number of a report, setting `width` from it if it is binary and
+/// the width is not known yet. Hex and octal need the width `declared`, a width
+/// taken from binary rows is not enough.
+fn parse_value(text: &str, line: usize, width: &mut Option<usize>, declared: bool) -> Result<(u128, u128), ReportError> {
+    let (digits, radix, prefix) = match text.get(..2) {
+        Some("0b") => (&text[2..], 2, 2),
+        Some("0o" | "0x") if !declared => return Err(ReportError::UndeclaredWidth { line }),
+        Some("0o") => (&text[2..], 8, 2),
+        Some("0x") => (&text[2..], 16, 2),
+        _ => (text, 2, 0),
+    };
+    if digits.is_empty() {
+        return Err(ReportError::NoDigits { line });
+    }
+    let parsed = match str_to_dec(digits, radix) {
+        Ok(parsed) => Some(parsed),
+        Err(DigitError::NotADigit(column, found)) => {
+            return Err(ReportError::InvalidDigit { line, column: prefix + column + 1, found, radix })
+        }
+        Err(DigitError::Overflow) => None,
+    };
+    if radix == 2 {
+        let bits = digits.chars().count();
+        if bits > MAX_WIDTH {
+            return Err(ReportError::TooWide { line, width: bits });
+        }
+        let expected = *width.get_or_insert(bits);
+        if bits != expected {
+            return Err(ReportError::Ragged { line, width: bits, expected });
+        }
+    }
+    let width = width.ok_or(ReportError::UndeclaredWidth { line })?;
+    match parsed {
+        Some((value, wildcards)) if value & !full_mask(width) == 0 => Ok((value, wildcards & full_mask(width))),
+        _ => Err(ReportError::DoesNotFit { line, width }),
+    }
 }
 
 pub struct Config {
     pub file_path: String,
+    pub criteria: BitCriteria,
 }
 
 impl Config {
@@ -20,143 +348,203 @@ impl Config {
             None => return Err("Didn't get a file path"),
         };
 
+        let criteria = match (args.next().as_deref(), args.next().as_deref()) {
+            (None, _) => BitCriteria::default(),
+            (Some("--ties"), Some("one")) => BitCriteria::new(TiePolicy::PreferOne),
+            (Some("--ties"), Some("zero")) => BitCriteria::new(TiePolicy::PreferZero),
+            (Some("--ties"), Some("error")) => BitCriteria::new(TiePolicy::Error),
+            _ => return Err("Expected --ties followed by 'one', 'zero' or 'error'"),
+        };
+
         Ok(Config {
             file_path,
+            criteria,
         })
     }
 }
 
-struct Report {
-    pc: PowerConsumption,
-    lsr: LifeSupportRating,
+/// How often 1 and 0 appear in one column of the report.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct ColumnCount {
+    pub ones: usize,
+    pub zeros: usize,
 }
 
-impl Report {}
+impl ColumnCount {
+    pub fn is_tie(&self) -> bool {
+        self.ones == self.zeros
+    }
+}
 
-struct PowerConsumption
+impl DiagnosticReport {
+    /// One count per column, leftmost column first. Unknown bits count as neither.
+    pub fn column_counts(&self) -> Vec<ColumnCount> {
+        (0..self.width)
+            .map(|column| {
+                let bit = self.column_bit(column);
+                let ones = self.rows.iter().filter(|&&row| row & bit != 0).count();
+                let unknown = self.wildcards.iter().filter(|&&wild| wild & bit != 0).count();
+                ColumnCount { ones, zeros: self.rows.len() - ones - unknown }
+            })
+            .collect()
+    }
+
+    /// Columns, counted from the left, where 1 and 0 are equally common. Masked
+    /// columns are left out, they never decide anything.
+    pub fn ties(&self) -> Vec<usize> {
+        self.column_counts()
+            .iter()
+            .enumerate()
+            .filter(|&(column, count)| count.is_tie() && self.includes(column))
+            .map(|(column, _)| column)
+            .collect()
+    }
+
+    pub fn gamma_rate(&self) -> Result<u128, ReportError> {
+        Ok(PowerConsumption::from(self)?.gamma_rate)
+    }
+
+    pub fn epsilon_rate(&self) -> Result<u128, ReportError> {
+        Ok(PowerConsumption::from(self)?.epsilon_rate)
+    }
+
+    pub fn oxygen_generator_rating(&self) -> Result<u128, ReportError> {
+        LifeSupportRating::filter(self, true)
+    }
+
+    pub fn co2_scrubber_rating(&self) -> Result<u128, ReportError> {
+        LifeSupportRating::filter(self, false)
+    }
+
+    /// `None` when the product does not fit in 128 bits, which only wide reports reach.
+    pub fn power_consumption(&self) -> Result<Option<u128>, ReportError> {
+        Ok(PowerConsumption::from(self)?.get())
+    }
+
+    /// `None` when the product does not fit in 128 bits, which only wide reports reach.
+    pub fn life_support_rating(&self) -> Result<Option<u128>, ReportError> {
+        Ok(LifeSupportRating::from(self)?.get())
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct PowerConsumption
 {
-    gamma_rate: u32,
-    epsilon_rate: u32,
+    pub gamma_rate: u128,
+    pub epsilon_rate: u128,
 }
 
 impl PowerConsumption {
-    fn from(&mut self, s: &str) -> &PowerConsumption {
-        let lines: Vec<&str> = s.split("\n").collect();
-        let mut count: [i32; 12] = [0; 12];
-        for line in lines {
-            for (i, c) in line.chars().enumerate() {
-                match c {
-                    '0' => count[i] -= 1,
-                    '1' => count[i] += 1,
-                    _ => continue
-                };
+    pub fn from(report: &DiagnosticReport) -> Result<PowerConsumption, ReportError> {
+        Self::from_counts(&report.column_counts(), report.criteria(), report.mask())
+    }
+
+    /// Gamma and epsilon from column counts however they were obtained, leftmost column
+    /// first. Columns outside `mask` are 0 in both rates.
+    pub fn from_counts(counts: &[ColumnCount], criteria: BitCriteria, mask: u128) -> Result<PowerConsumption, ReportError> {
+        let mut gamma_rate = 0;
+        let mut epsilon_rate = 0;
+        for (column, &count) in counts.iter().enumerate() {
+            let bit = 1 << (counts.len() - 1 - column);
+            if mask & bit == 0 {
+                continue;
             }
-        }
-        let mut g = "".to_string();
-        for x in count {
-            if x < 0 {
-                g.push('0');
-            } else if x > 0 {
-                g.push('1');
+            match criteria.most_common(count, column)? {
+                false => epsilon_rate |= bit,
+                true => gamma_rate |= bit,
             }
         }
-        let e: String =
-            g
-                .chars()
-                .map(|c| match c {
-                    '0' => '1',
-                    _ => '0',
-                })
-                .collect();
-        self.gamma_rate = binstr_to_dec(&g).expect("Not a binary number!");
-        self.epsilon_rate = binstr_to_dec(&e).expect("Not a binary number!");
-        self
+        Ok(PowerConsumption { gamma_rate, epsilon_rate })
     }
 
-    fn get(&self) -> u32 {
-        self.gamma_rate * self.epsilon_rate
+    pub fn get(&self) -> Option<u128> {
+        self.gamma_rate.checked_mul(self.epsilon_rate)
     }
 }
 
-struct LifeSupportRating
+#[derive(Debug, PartialEq)]
+pub struct LifeSupportRating
 {
-    co2_rating: u32,
-    o_gen_rating: u32,
+    /// Found by keeping the most common bit in each column.
+    pub oxygen_generator_rating: u128,
+    /// Found by keeping the least common bit in each column.
+    pub co2_scrubber_rating: u128,
 }
 
 impl LifeSupportRating {
-    fn from(&mut self, s: &str, bits: u32) -> &LifeSupportRating {
-        let lines: Vec<u32> = s.split("\r\n").map(|x| binstr_to_dec(x).expect("")).collect::<Vec<_>>();
-        let base: u32 = 2;
-        let mut most = lines.clone();
-        for i in 0..bits {
-            let mut count: i32 = 0;
-            for line in &most {
-                if (*line & base.pow(bits-1-i)) > 0 {
-                    count += 1;
-                } else {
-                    count -= 1;
-                }
-            }
-            if count >= 0 {
-                most = most.into_iter().filter(|n| (*n & base.pow(bits-1-i)) > 0).collect::<Vec<_>>();
-            } else {
-                most = most.into_iter().filter(|n| (*n & base.pow(bits-1-i)) == 0).collect::<Vec<_>>();
-            }
-            if most.len() == 1 {
-                break;
-            }
-        }
-        self.co2_rating = *most.first().expect("no number");
+    pub fn from(report: &DiagnosticReport) -> Result<LifeSupportRating, ReportError> {
+        Ok(LifeSupportRating {
+            oxygen_generator_rating: Self::filter(report, true)?,
+            co2_scrubber_rating: Self::filter(report, false)?,
+        })
+    }
 
-        let mut least = lines.clone();
-        for i in 0..bits {
-            let mut count: i32 = 0;
-            for line in &least {
-                if (*line & base.pow(bits-1-i)) > 0 {
-                    count += 1;
-                } else {
-                    count -= 1;
-                }
+    /// Narrows the rows down column by column until one is left, skipping masked
+    /// columns. Rows with an unknown bit stay in whichever bit is kept, and that bit
+    /// is what the rating shows for it. Rows that survive every column agree on all
+    /// of them, so any of them is the rating; masked columns read as 0.
+    fn filter(report: &DiagnosticReport, most_common: bool) -> Result<u128, ReportError> {
+        let mut candidates: Vec<(u128, u128)> =
+            report.rows().iter().enumerate().map(|(i, &row)| (row, report.wildcard(i))).collect();
+        let (mut kept, mut decided) = (0, 0);
+        for column in (0..report.width()).filter(|&column| report.includes(column)) {
+            if candidates.len() == 1 {
+                break;
             }
-            if count >= 0 {
-                least = least.into_iter().filter(|n| (*n & base.pow(bits-1-i)) == 0).collect::<Vec<_>>();
-            } else {
-                least = least.into_iter().filter(|n| (*n & base.pow(bits-1-i)) > 0).collect::<Vec<_>>();
+            let bit = report.column_bit(column);
+            let ones = candidates.iter().filter(|&&(row, _)| row & bit != 0).count();
+            let unknown = candidates.iter().filter(|&&(_, wild)| wild & bit != 0).count();
+            let count = ColumnCount { ones, zeros: candidates.len() - ones - unknown };
+            let keep_ones = report.criteria().most_common(count, column)? == most_common;
+            candidates.retain(|&(row, wild)| wild & bit != 0 || (row & bit != 0) == keep_ones);
+            if candidates.is_empty() {
+                return Err(ReportError::NoCandidates { rating: rating_name(most_common), column });
             }
-            if least.len() == 1 {
-                break;
+            decided |= bit;
+            if keep_ones {
+                kept |= bit;
             }
         }
-        self.o_gen_rating = *least.first().expect("no number");
-        println!("{}/{}",self.o_gen_rating, self.co2_rating);
-        self
+        Ok((candidates[0].0 & !decided | kept) & report.mask())
+    }
+
+    pub fn get(&self) -> Option<u128> {
+        self.oxygen_generator_rating.checked_mul(self.co2_scrubber_rating)
     }
+}
+
+fn rating_name(most_common: bool) -> &'static str {
+    if most_common { "oxygen generator" } else { "CO2 scrubber" }
+}
 
-    fn get(&self) -> u32 {
-        self.o_gen_rating * self.co2_rating
+fn print_product(name: &str, product: Option<u128>) {
+    match product {
+        Some(answer) => println!("{} {}", name, answer),
+        None => println!("{} does not fit in 128 bits", name),
     }
 }
 
 pub fn run(config: Config) -> Result<(), Box<dyn Error>>
 {
-    let mut rep = Report
-    {
-        pc: PowerConsumption
-        {
-            gamma_rate: 0,
-            epsilon_rate: 0,
-        },
-        lsr: LifeSupportRating
-        {
-            co2_rating: 0,
-            o_gen_rating: 0,
-        },
-    };
     let content = fs::read_to_string(config.file_path)?;
+    let report = DiagnosticReport::parse(&content)?.with_criteria(config.criteria);
+
+    let threads = thread::available_parallelism().map_or(1, |n| n.get());
+    let pc = bitslice::power_consumption(&report, threads)?;
+    println!("Gamma rate {}, epsilon rate {}", pc.gamma_rate, pc.epsilon_rate);
+    print_product("Power consumption", pc.get());
 
-    println!("Answer {}", rep.pc.from(&content).get());
-    println!("Answer {}", rep.lsr.from(&content,12).get());
+    let lsr = LifeSupportRating::from(&report)?;
+    println!(
+        "Oxygen generator rating {}, CO2 scrubber rating {}",
+        lsr.oxygen_generator_rating, lsr.co2_scrubber_rating
+    );
+    print_product("Life support rating", lsr.get());
+
+    let ties = report.ties();
+    if !ties.is_empty() {
+        println!("Tied columns {:?}", ties);
+    }
     Ok(())
 }
 
@@ -179,10 +567,202 @@ mod tests {
 11001
 00010
 01010";
-        let mut lsr = LifeSupportRating{
-            co2_rating: 0,
-            o_gen_rating: 0
-        };
-        assert_eq!(230, lsr.from(content,5).get());
+        let report = DiagnosticReport::parse(content).unwrap();
+        assert_eq!(Some(230), LifeSupportRating::from(&report).unwrap().get());
+    }
+
+    #[test]
+    fn all_ratings() {
+        // numbers from website https://adventofcode.com/2021/day/3
+        let report = DiagnosticReport::parse("00100\n11110\n10110\n10111\n10101\n01111\n00111\n11100\n10000\n11001\n00010\n01010").unwrap();
+        assert_eq!(Ok(22), report.gamma_rate());
+        assert_eq!(Ok(9), report.epsilon_rate());
+        assert_eq!(Ok(Some(198)), report.power_consumption());
+        assert_eq!(Ok(23), report.oxygen_generator_rating());
+        assert_eq!(Ok(10), report.co2_scrubber_rating());
+        assert_eq!(Ok(Some(230)), report.life_support_rating());
+        assert_eq!(ColumnCount { ones: 7, zeros: 5 }, report.column_counts()[0]);
+        assert_eq!(ColumnCount { ones: 5, zeros: 7 }, report.column_counts()[4]);
+        assert!(report.ties().is_empty());
+    }
+
+    #[test]
+    fn reports_ties() {
+        let report = DiagnosticReport::parse("101\n011").unwrap();
+        assert_eq!(vec![0, 1], report.ties());
+    }
+
+    #[test]
+    fn tie_policies() {
+        let report = DiagnosticReport::parse("01\n10").unwrap();
+        let pc = PowerConsumption::from(&report).unwrap();
+        assert_eq!((0b11, 0b00), (pc.gamma_rate, pc.epsilon_rate));
+        let lsr = LifeSupportRating::from(&report).unwrap();
+        assert_eq!((0b10, 0b01), (lsr.oxygen_generator_rating, lsr.co2_scrubber_rating));
+
+        let report = report.with_criteria(BitCriteria::new(TiePolicy::PreferZero));
+        let pc = PowerConsumption::from(&report).unwrap();
+        assert_eq!((0b00, 0b11), (pc.gamma_rate, pc.epsilon_rate));
+        let lsr = LifeSupportRating::from(&report).unwrap();
+        assert_eq!((0b01, 0b10), (lsr.oxygen_generator_rating, lsr.co2_scrubber_rating));
+
+        let report = report.with_criteria(BitCriteria::new(TiePolicy::Error));
+        assert_eq!(Err(ReportError::Tie { column: 0 }), report.gamma_rate());
+        assert_eq!(Err(ReportError::Tie { column: 0 }), report.oxygen_generator_rating());
+    }
+
+    #[test]
+    fn every_filter_step_ties() {
+        // all 4-bit numbers: each column ties, and keeps tying after every filter step
+        let content: Vec<String> = (0..16).map(|n| format!("{:04b}", n)).collect();
+        let report = DiagnosticReport::parse(&content.join("\n")).unwrap();
+        assert_eq!(vec![0, 1, 2, 3], report.ties());
+        assert_eq!(Ok(0b1111), report.oxygen_generator_rating());
+        assert_eq!(Ok(0b0000), report.co2_scrubber_rating());
+
+        let report = report.with_criteria(BitCriteria::new(TiePolicy::PreferZero));
+        assert_eq!(Ok(0b0000), report.oxygen_generator_rating());
+        assert_eq!(Ok(0b1111), report.co2_scrubber_rating());
+
+        // the tie only shows up in the last column once the others have been filtered
+        let report = DiagnosticReport::parse("110\n100\n011\n001\n111")
+            .unwrap()
+            .with_criteria(BitCriteria::new(TiePolicy::Error));
+        assert_eq!(Ok(0b111), report.gamma_rate());
+        assert_eq!(Err(ReportError::Tie { column: 2 }), report.oxygen_generator_rating());
+    }
+
+    #[test]
+    fn no_candidates_left() {
+        let report = DiagnosticReport::parse("101\n101").unwrap();
+        assert_eq!(Ok(0b101), report.oxygen_generator_rating());
+        assert_eq!(Err(ReportError::NoCandidates { rating: "CO2 scrubber", column: 0 }), report.co2_scrubber_rating());
+    }
+
+    #[test]
+    fn single_row_is_every_rating() {
+        let report = DiagnosticReport::parse("0110").unwrap();
+        assert_eq!(Ok(0b0110), report.oxygen_generator_rating());
+        assert_eq!(Ok(0b0110), report.co2_scrubber_rating());
+    }
+
+    #[test]
+    fn from_rows() {
+        assert_eq!(DiagnosticReport::parse("0110\n0001"), DiagnosticReport::from_rows(4, vec![0b0110, 0b0001]));
+        assert_eq!(Err(ReportError::Ragged { line: 2, width: 5, expected: 4 }),
+                   DiagnosticReport::from_rows(4, vec![0b0110, 0b10001]));
+    }
+
+    #[test]
+    fn width_is_inferred() {
+        let report = DiagnosticReport::parse("0110\n0100\n1111").unwrap();
+        assert_eq!(4, report.width());
+        assert_eq!(Ok(Some(6 * 9)), report.power_consumption());
+
+        let wide = format!("{}\n{}\n{}", "1".repeat(100), "10".repeat(50), "1".repeat(100));
+        let pc = PowerConsumption::from(&DiagnosticReport::parse(&wide).unwrap()).unwrap();
+        assert_eq!((1u128 << 100) - 1, pc.gamma_rate);
+        assert_eq!(0, pc.epsilon_rate);
+    }
+
+    #[test]
+    fn line_endings_do_not_matter() {
+        let lf = DiagnosticReport::parse("00100\n11110\n10110").unwrap();
+        assert_eq!(vec![0b00100, 0b11110, 0b10110], lf.rows());
+        assert_eq!(lf, DiagnosticReport::parse("00100\r\n11110\r\n10110\r\n").unwrap());
+        assert_eq!(lf, DiagnosticReport::parse("00100\n11110\n10110\n").unwrap());
+        assert_eq!(lf, DiagnosticReport::parse("\n  00100 \n\t11110\r\n10110\n\n").unwrap());
+        assert_eq!(Err(ReportError::Ragged { line: 3, width: 4, expected: 5 }),
+                   DiagnosticReport::parse("\n00100\r\n1111\r\n"));
+    }
+
+    #[test]
+    fn prefixed_values() {
+        let report = DiagnosticReport::parse("width 8\n0xff\n0o17\n0b00001111\n00110011\n0x3").unwrap();
+        assert_eq!(8, report.width());
+        assert_eq!(vec![0xff, 0o17, 0b1111, 0b110011, 0x3], report.rows());
+        assert_eq!(DiagnosticReport::parse("0b101\n011"), DiagnosticReport::parse("101\n0b011"));
+    }
+
+    #[test]
+    fn wildcards() {
+        let report = DiagnosticReport::parse("width 8\n0xx3\n0o3x1").unwrap();
+        assert_eq!(vec![0x03, 0o301], report.rows());
+        assert_eq!(vec![0xf0, 0o070], report.wildcards());
+
+        // `1x` is counted in neither bit of the last column, but stays in the filter
+        let report = DiagnosticReport::parse("1x\n10\n01").unwrap();
+        assert_eq!(vec![ColumnCount { ones: 2, zeros: 1 }, ColumnCount { ones: 1, zeros: 1 }], report.column_counts());
+        assert_eq!(Ok(0b10), report.oxygen_generator_rating());
+        assert_eq!(Ok(0b01), report.co2_scrubber_rating());
+        // the unknown bit reads as the bit the filter kept for it
+        let report = DiagnosticReport::parse("1x\n11\n01\n00").unwrap();
+        assert_eq!(Ok(0b11), report.oxygen_generator_rating());
+
+        // `0x` is always a prefix, so without a width line the row is rejected
+        assert_eq!(Err(ReportError::UndeclaredWidth { line: 2 }), DiagnosticReport::parse("101\n0x1\n011"));
+        assert_eq!(Err(ReportError::UndeclaredWidth { line: 1 }), DiagnosticReport::parse("0x1\n101\n011"));
+        // and the wildcard row needs `0b`
+        let report = DiagnosticReport::parse("101\n0b0x1\n011").unwrap();
+        assert_eq!(vec![0b101, 0b001, 0b011], report.rows());
+        assert_eq!(vec![0, 0b010, 0], report.wildcards());
+        let report = DiagnosticReport::parse("width 3\n101\n0b0x1\n0x3").unwrap();
+        assert_eq!(vec![0b101, 0b001, 0b011], report.rows());
+        assert_eq!(vec![0, 0b010, 0], report.wildcards());
+    }
+
+    #[test]
+    fn masked_columns_are_left_out() {
+        let rows = ["00100", "11110", "10110", "10111", "10101", "01111", "00111", "11100", "10000", "11001", "00010", "01010"];
+        let full = DiagnosticReport::parse(&rows.join("\n")).unwrap();
+        for column in 0..5 {
+            let mask: String = (0..5).map(|c| if c == column { '0' } else { '1' }).collect();
+            let masked = DiagnosticReport::parse(&format!("mask {}\n{}", mask, rows.join("\n"))).unwrap();
+            assert_eq!(full.clone().with_mask(u128::from_str_radix(&mask, 2).unwrap()), masked);
+
+            // same as dropping the column and putting a 0 back in its place
+            let dropped: Vec<String> = rows.iter().map(|row| format!("{}{}", &row[..column], &row[column + 1..])).collect();
+            let dropped = DiagnosticReport::parse(&dropped.join("\n")).unwrap();
+            let widen = |rating: Result<u128, ReportError>| {
+                let low = 4 - column;
+                let shift = |c: usize| if c >= column { c + 1 } else { c };
+                rating.map(|value| (value >> low) << (low + 1) | value & ((1 << low) - 1)).map_err(|e| match e {
+                    ReportError::NoCandidates { rating, column } => ReportError::NoCandidates { rating, column: shift(column) },
+                    ReportError::Tie { column } => ReportError::Tie { column: shift(column) },
+                    e => e,
+                })
+            };
+            assert_eq!(widen(dropped.gamma_rate()), masked.gamma_rate(), "column {}", column);
+            assert_eq!(widen(dropped.epsilon_rate()), masked.epsilon_rate(), "column {}", column);
+            assert_eq!(widen(dropped.oxygen_generator_rating()), masked.oxygen_generator_rating(), "column {}", column);
+            assert_eq!(widen(dropped.co2_scrubber_rating()), masked.co2_scrubber_rating(), "column {}", column);
+        }
+    }
+
+    #[test]
+    fn invalid_headers_and_values() {
+        assert_eq!(Err(ReportError::UndeclaredWidth { line: 1 }), DiagnosticReport::parse("0x1f"));
+        assert_eq!(Err(ReportError::DoesNotFit { line: 2, width: 4 }), DiagnosticReport::parse("width 4\n0x1f"));
+        assert_eq!(Err(ReportError::Ragged { line: 2, width: 3, expected: 4 }), DiagnosticReport::parse("width 4\n0b101"));
+        assert_eq!(Err(ReportError::NoDigits { line: 2 }), DiagnosticReport::parse("width 4\n0x"));
+        assert_eq!(Err(ReportError::InvalidDigit { line: 2, column: 3, found: 'g', radix: 16 }),
+                   DiagnosticReport::parse("width 8\n0xg1"));
+        assert_eq!(Err(ReportError::InvalidDigit { line: 2, column: 4, found: '8', radix: 8 }), DiagnosticReport::parse("width 6\n0o78"));
+        assert_eq!(Err(ReportError::UndeclaredWidth { line: 1 }), DiagnosticReport::parse("0o78"));
+        assert!(matches!(DiagnosticReport::parse("01\nwidth 2"), Err(ReportError::Header { line: 2, .. })));
+        assert!(matches!(DiagnosticReport::parse("width 129\n01"), Err(ReportError::Header { line: 1, .. })));
+        assert!(matches!(DiagnosticReport::parse("mask 1x\n01"), Err(ReportError::Header { line: 1, .. })));
+        assert_eq!(Err(ReportError::Empty), DiagnosticReport::parse("width 4\nmask 0xf"));
+    }
+
+    #[test]
+    fn invalid_reports() {
+        assert_eq!(Err(ReportError::Empty), DiagnosticReport::parse(" \n\n"));
+        assert_eq!(Err(ReportError::Ragged { line: 2, width: 3, expected: 4 }), DiagnosticReport::parse("0110\n010"));
+        assert_eq!(Err(ReportError::InvalidDigit { line: 1, column: 2, found: '2', radix: 2 }), DiagnosticReport::parse("0210"));
+        let too_wide = "1".repeat(129);
+        assert_eq!(Err(ReportError::TooWide { line: 1, width: 129 }), DiagnosticReport::parse(&too_wide));
+        assert_eq!("line 2 is 3 bits wide, the lines before are 4",
+                   ReportError::Ragged { line: 2, width: 3, expected: 4 }.to_string());
     }
 }
\ No newline at end of file
@@ -0,0 +1,29 @@
+//! Random reports for the tests and the benchmarks, which include this file with
+//! `#[path]` since they cannot see the library's test code.
+#![allow(dead_code)]
+
+use std::time::{Duration, Instant};
+
+/// xorshift64*, good enough to generate test and benchmark reports.
+pub fn random(state: &mut u64) -> u64 {
+    *state ^= *state >> 12;
+    *state ^= *state << 25;
+    *state ^= *state >> 27;
+    state.wrapping_mul(0x2545_f491_4f6c_dd1d)
+}
+
+/// `rows` random rows of `width` bits.
+pub fn random_rows(state: &mut u64, width: usize, rows: usize) -> Vec<u128> {
+    let mask = if width >= 128 { u128::MAX } else { (1u128 << width) - 1 };
+    (0..rows).map(|_| ((random(state) as u128) << 64 | random(state) as u128) & mask).collect()
+}
+
+/// The average time of `runs` calls to `f` after a warm-up call, and its result.
+pub fn time<T>(runs: u32, mut f: impl FnMut() -> T) -> (Duration, T) {
+    let mut result = f();
+    let start = Instant::now();
+    for _ in 0..runs {
+        result = f();
+    }
+    (start.elapsed() / runs, result)
+}
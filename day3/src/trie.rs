@@ -0,0 +1,196 @@
+//! Life support search on a binary prefix trie.
+//!
+//! `LifeSupportRating::from` filters a copy of the rows once per column and rating.
+//! The trie stores how many rows share every prefix, so both filters become a walk
+//! from the root: O(n·bits) to build, then O(bits) per rating. Building costs more
+//! than a single filtering pass (see `benches/life_support.rs`), so the trie pays
+//! off when the ratings are asked for repeatedly, e.g. under several tie policies.
+//!
+//! Masked columns get no level in the trie. A row with an unknown bit goes down
+//! both branches, which adds the same count to both children and so leaves every
+//! most common bit as the filter sees it; it does double the row's paths, which is
+//! why rows are limited to `MAX_WILDCARDS` unknown bits.
+
+use crate::{rating_name, BitCriteria, ColumnCount, DiagnosticReport, LifeSupportRating, ReportError};
+
+const NONE: u32 = 0;
+
+/// Unknown bits a single row may have in the columns the ratings look at.
+pub const MAX_WILDCARDS: u32 = 16;
+
+#[derive(Debug, Clone, Copy)]
+struct Node {
+    /// Index of the child for a 0 and a 1 bit, `NONE` if no row continues that way.
+    children: [u32; 2],
+    /// Rows sharing the prefix that leads to this node.
+    count: u32,
+}
+
+pub struct BinaryTrie {
+    width: usize,
+    /// The unmasked columns, one per level.
+    columns: Vec<usize>,
+    nodes: Vec<Node>,
+}
+
+impl BinaryTrie {
+    /// Reports are limited to `u32::MAX` rows, which keeps the nodes small.
+    pub fn build(report: &DiagnosticReport) -> Result<BinaryTrie, ReportError> {
+        let columns: Vec<usize> = (0..report.width()).filter(|&column| report.includes(column)).collect();
+        let mut nodes = vec![Node { children: [NONE; 2], count: 0 }];
+        let (mut level, mut next) = (vec![], vec![]);
+        for (i, &row) in report.rows().iter().enumerate() {
+            let wild = report.wildcard(i) & report.mask();
+            if wild.count_ones() > MAX_WILDCARDS {
+                return Err(ReportError::TooManyWildcards { line: i + 1, count: wild.count_ones() });
+            }
+            nodes[0].count += 1;
+            level.clear();
+            level.push(0);
+            for &column in &columns {
+                let bit = report.column_bit(column);
+                let bits: &[usize] = match (wild & bit != 0, row & bit != 0) {
+                    (true, _) => &[0, 1],
+                    (false, one) => &[one as usize],
+                };
+                for &node in &level {
+                    for &b in bits {
+                        if nodes[node].children[b] == NONE {
+                            nodes.push(Node { children: [NONE; 2], count: 0 });
+                            nodes[node].children[b] = (nodes.len() - 1) as u32;
+                        }
+                        let child = nodes[node].children[b] as usize;
+                        nodes[child].count += 1;
+                        next.push(child);
+                    }
+                }
+                std::mem::swap(&mut level, &mut next);
+                next.clear();
+            }
+        }
+        Ok(BinaryTrie { width: report.width(), columns, nodes })
+    }
+
+    fn count(&self, child: u32) -> usize {
+        match child {
+            NONE => 0,
+            child => self.nodes[child as usize].count as usize,
+        }
+    }
+
+    /// Same rules as `LifeSupportRating::from`: keep the most (or least) common bit
+    /// until a single row is left, then follow it, taking 0 for its unknown bits.
+    fn search(&self, criteria: BitCriteria, most_common: bool) -> Result<u128, ReportError> {
+        let mut node = 0;
+        let mut value = 0u128;
+        for &column in &self.columns {
+            let [zeros, ones] = self.nodes[node].children;
+            let bit = if self.nodes[node].count == 1 {
+                // A single row left, follow it to the end.
+                zeros == NONE
+            } else {
+                let count = ColumnCount { ones: self.count(ones), zeros: self.count(zeros) };
+                criteria.most_common(count, column)? == most_common
+            };
+            let next = self.nodes[node].children[bit as usize];
+            if next == NONE {
+                return Err(ReportError::NoCandidates { rating: rating_name(most_common), column });
+            }
+            value |= (bit as u128) << (self.width - 1 - column);
+            node = next as usize;
+        }
+        Ok(value)
+    }
+
+    pub fn oxygen_generator_rating(&self, criteria: BitCriteria) -> Result<u128, ReportError> {
+        self.search(criteria, true)
+    }
+
+    pub fn co2_scrubber_rating(&self, criteria: BitCriteria) -> Result<u128, ReportError> {
+        self.search(criteria, false)
+    }
+
+    pub fn life_support_rating(&self, criteria: BitCriteria) -> Result<LifeSupportRating, ReportError> {
+        Ok(LifeSupportRating {
+            oxygen_generator_rating: self.oxygen_generator_rating(criteria)?,
+            co2_scrubber_rating: self.co2_scrubber_rating(criteria)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{random, random_rows};
+    use crate::TiePolicy;
+
+    fn random_report(state: &mut u64, width: usize, rows: usize) -> DiagnosticReport {
+        DiagnosticReport::from_rows(width, random_rows(state, width, rows)).unwrap()
+    }
+
+    #[test]
+    fn puzzle_example() {
+        let report = DiagnosticReport::parse("00100\n11110\n10110\n10111\n10101\n01111\n00111\n11100\n10000\n11001\n00010\n01010").unwrap();
+        let trie = BinaryTrie::build(&report).unwrap();
+        assert_eq!(Ok(23), trie.oxygen_generator_rating(BitCriteria::default()));
+        assert_eq!(Ok(10), trie.co2_scrubber_rating(BitCriteria::default()));
+    }
+
+    #[test]
+    fn matches_filtering_on_random_reports() {
+        let mut state = 0x9e37_79b9_7f4a_7c15;
+        let policies = [TiePolicy::PreferOne, TiePolicy::PreferZero, TiePolicy::Error];
+        for (width, rows) in [(1, 1), (1, 5), (3, 2), (5, 12), (8, 300), (12, 1000), (40, 10_000), (128, 1000), (4, 100_000), (20, 1_000_000)] {
+            let report = random_report(&mut state, width, rows);
+            let trie = BinaryTrie::build(&report).unwrap();
+            for tie in policies {
+                let criteria = BitCriteria::new(tie);
+                let report = report.clone().with_criteria(criteria);
+                assert_eq!(report.oxygen_generator_rating(), trie.oxygen_generator_rating(criteria),
+                           "oxygen, width {} rows {} {:?}", width, rows, tie);
+                assert_eq!(report.co2_scrubber_rating(), trie.co2_scrubber_rating(criteria),
+                           "co2, width {} rows {} {:?}", width, rows, tie);
+            }
+        }
+    }
+
+    #[test]
+    fn matches_filtering_with_wildcards_and_masks() {
+        let mut state = 7;
+        for (width, rows) in [(1, 3), (3, 10), (6, 50), (12, 2000), (40, 20_000)] {
+            let report = random_report(&mut state, width, rows);
+            // about one unknown bit in eight
+            let wildcards = (0..rows).map(|_| (random(&mut state) & random(&mut state) & random(&mut state)) as u128).collect();
+            let mask = random(&mut state) as u128;
+            for report in [report.clone().with_wildcards(wildcards), report.with_mask(mask)] {
+                let trie = BinaryTrie::build(&report).unwrap();
+                assert_eq!(report.oxygen_generator_rating(), trie.oxygen_generator_rating(report.criteria()), "width {}", width);
+                assert_eq!(report.co2_scrubber_rating(), trie.co2_scrubber_rating(report.criteria()), "width {}", width);
+                let report = report.with_mask(mask);
+                let trie = BinaryTrie::build(&report).unwrap();
+                assert_eq!(report.oxygen_generator_rating(), trie.oxygen_generator_rating(report.criteria()), "width {}", width);
+                assert_eq!(report.co2_scrubber_rating(), trie.co2_scrubber_rating(report.criteria()), "width {}", width);
+            }
+        }
+    }
+
+    #[test]
+    fn too_many_wildcards() {
+        let report = DiagnosticReport::parse(&format!("{}\n{}", "0".repeat(20), "x".repeat(20))).unwrap();
+        assert!(matches!(BinaryTrie::build(&report), Err(ReportError::TooManyWildcards { line: 2, count: 20 })));
+        // masked columns do not count
+        assert!(BinaryTrie::build(&report.with_mask(0xffff)).is_ok());
+    }
+
+    #[test]
+    fn matches_filtering_with_duplicates() {
+        let mut state = 42;
+        for rows in [2, 3, 7, 50] {
+            // few distinct values, so many rows are repeated and filters run dry
+            let report = random_report(&mut state, 3, rows);
+            let trie = BinaryTrie::build(&report).unwrap();
+            assert_eq!(report.oxygen_generator_rating(), trie.oxygen_generator_rating(BitCriteria::default()));
+            assert_eq!(report.co2_scrubber_rating(), trie.co2_scrubber_rating(BitCriteria::default()));
+        }
+    }
+}
@@ -0,0 +1,90 @@
+//! Compares the indexed `Bingo::play` with the previous approach of marking every
+//! board on every draw and rescanning its rows and columns.
+//! Run with `cargo bench --bench play`.
+
+use std::time::{Duration, Instant};
+
+use day4::{Bingo, Strategy};
+
+/// xorshift64*, good enough to generate benchmark games.
+fn random(state: &mut u64) -> u64 {
+    *state ^= *state >> 12;
+    *state ^= *state << 25;
+    *state ^= *state >> 27;
+    state.wrapping_mul(0x2545_f491_4f6c_dd1d)
+}
+
+fn shuffled(state: &mut u64, n: u32) -> Vec<u32> {
+    let mut numbers: Vec<u32> = (0..n).collect();
+    for i in (1..numbers.len()).rev() {
+        numbers.swap(i, random(state) as usize % (i + 1));
+    }
+    numbers
+}
+
+/// A game in the input format with `boards` 5x5 boards of distinct numbers below `range`.
+fn game(state: &mut u64, boards: usize, range: u32) -> String {
+    let numbers = shuffled(state, range).iter().map(|n| n.to_string()).collect::<Vec<_>>().join(",");
+    let mut content = numbers + "\n";
+    for _ in 0..boards {
+        content.push('\n');
+        let cells = shuffled(state, range);
+        for row in cells[..25].chunks(5) {
+            content.push_str(&row.iter().map(|n| format!("{:3}", n)).collect::<Vec<_>>().join(" "));
+            content.push('\n');
+        }
+    }
+    content
+}
+
+/// The previous `play`: every board is scanned for every draw, first match only.
+fn play_scanning(content: &str, lose: bool) -> Option<(u32, u64)> {
+    let (numbers, boards) = content.split_once("\n\n").unwrap();
+    let numbers: Vec<u32> = numbers.split(',').map(|x| x.parse().unwrap()).collect();
+    let mut boards: Vec<Vec<Vec<(u32, bool)>>> = boards
+        .split("\n\n")
+        .map(|b| b.lines().filter(|l| !l.trim().is_empty()).map(|l| l.split_whitespace().map(|x| (x.parse().unwrap(), false)).collect()).collect())
+        .collect();
+    let mut winners = vec![];
+    let count = boards.len();
+    for &number in &numbers {
+        for (n, board) in boards.iter_mut().enumerate() {
+            if let Some(field) = board.iter_mut().flatten().find(|field| field.0 == number) {
+                field.1 = true;
+            }
+            let bingo = (0..5).any(|i| (0..5).all(|j| board[i][j].1) || (0..5).all(|j| board[j][i].1));
+            if bingo && !winners.contains(&n) {
+                winners.push(n);
+                if !lose || winners.len() == count {
+                    let unmarked: u32 = board.iter().flatten().filter(|f| !f.1).map(|f| f.0).sum();
+                    return Some((number, unmarked as u64 * number as u64));
+                }
+            }
+        }
+    }
+    None
+}
+
+fn time<T>(runs: u32, mut f: impl FnMut() -> T) -> (Duration, T) {
+    let mut result = f();
+    let start = Instant::now();
+    for _ in 0..runs {
+        result = f();
+    }
+    (start.elapsed() / runs, result)
+}
+
+fn main() {
+    let mut state = 0x9e37_79b9_7f4a_7c15;
+    println!("{:>7} {:>6} {:>12} {:>12}", "boards", "range", "scanning", "indexed");
+    for (boards, range) in [(100, 100), (1_000, 100), (5_000, 100), (5_000, 1_000)] {
+        let content = game(&mut state, boards, range);
+        let bingo = Bingo::builder(&content).unwrap();
+
+        let (scanning, expected) = time(1, || play_scanning(&content, true));
+        let (indexed, actual) = time(3, || bingo.play(Strategy::Lose).ok().map(|f| (f.number, f.score)));
+        assert_eq!(expected, actual);
+        assert_eq!(play_scanning(&content, false), bingo.play(Strategy::Win).ok().map(|f| (f.number, f.score)));
+        println!("{:>7} {:>6} {:>12?} {:>12?}", boards, range, scanning, indexed);
+    }
+}
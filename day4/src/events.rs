@@ -0,0 +1,158 @@
+//! The event log of a game, one event per line:
+//!
+//! ```text
+//! draw 24
+//! mark 2 0,3
+//! win 2 rows
+//! ```
+//!
+//! `mark` lists the cells, as row,column, that a draw newly marked on a board, and
+//! `win` names the pattern the board completed. Blank lines are skipped.
+
+use std::fmt::Formatter;
+
+use crate::pattern::{Cell, WinPattern};
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Event {
+    Drawn { number: u32 },
+    Marked { board: usize, cells: Vec<Cell> },
+    Won { board: usize, pattern: WinPattern },
+}
+
+/// Events in the order they happened. Events are only ever appended.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct EventLog {
+    events: Vec<Event>,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum LogError {
+    Syntax { line: usize, problem: &'static str },
+    /// An event that cannot happen in the game at this point of the log.
+    Diverges { event: usize, problem: &'static str },
+    OutOfRange { index: usize, len: usize },
+}
+
+impl std::fmt::Display for LogError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LogError::Syntax { line, problem } => write!(f, "line {}: {}", line, problem),
+            LogError::Diverges { event, problem } => write!(f, "event {}: {}", event, problem),
+            LogError::OutOfRange { index, len } => write!(f, "event {} is past the end of a log of {}", index, len),
+        }
+    }
+}
+
+impl std::error::Error for LogError {}
+
+impl EventLog {
+    pub fn new() -> EventLog {
+        EventLog::default()
+    }
+
+    pub fn push(&mut self, event: Event) {
+        self.events.push(event);
+    }
+
+    pub fn events(&self) -> &[Event] {
+        &self.events
+    }
+
+    pub fn len(&self) -> usize {
+        self.events.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+
+    /// Reads a log written by `Display`. Win patterns are looked up by name in
+    /// `patterns`, the patterns of the game the log belongs to.
+    pub fn parse(s: &str, patterns: &[WinPattern]) -> Result<EventLog, LogError> {
+        let mut log = EventLog::new();
+        for (line, text) in s.lines().enumerate().map(|(i, text)| (i + 1, text.trim())) {
+            if text.is_empty() {
+                continue;
+            }
+            let syntax = |problem| LogError::Syntax { line, problem };
+            let (kind, rest) = text.split_once(' ').unwrap_or((text, ""));
+            let (first, rest) = rest.trim().split_once(' ').unwrap_or((rest.trim(), ""));
+            let event = match kind {
+                "draw" => Event::Drawn { number: first.parse().map_err(|_| syntax("expected a number after draw"))? },
+                "mark" => Event::Marked {
+                    board: first.parse().map_err(|_| syntax("expected a board after mark"))?,
+                    cells: rest
+                        .split_whitespace()
+                        .map(|cell| {
+                            let (i, j) = cell.split_once(',')?;
+                            Some((i.parse().ok()?, j.parse().ok()?))
+                        })
+                        .collect::<Option<Vec<_>>>()
+                        .filter(|cells| !cells.is_empty())
+                        .ok_or(syntax("expected cells as row,column after the board"))?,
+                },
+                "win" => Event::Won {
+                    board: first.parse().map_err(|_| syntax("expected a board after win"))?,
+                    pattern: patterns
+                        .iter()
+                        .find(|pattern| pattern.to_string() == rest.trim())
+                        .cloned()
+                        .ok_or(syntax("not a pattern of this game"))?,
+                },
+                _ => return Err(syntax("expected draw, mark or win")),
+            };
+            log.push(event);
+        }
+        Ok(log)
+    }
+}
+
+impl std::fmt::Display for Event {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Event::Drawn { number } => write!(f, "draw {}", number),
+            Event::Marked { board, cells } => {
+                write!(f, "mark {}", board)?;
+                cells.iter().try_for_each(|(i, j)| write!(f, " {},{}", i, j))
+            }
+            Event::Won { board, pattern } => write!(f, "win {} {}", board, pattern),
+        }
+    }
+}
+
+impl std::fmt::Display for EventLog {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        self.events.iter().try_for_each(|event| writeln!(f, "{}", event))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let patterns = vec![WinPattern::Rows, WinPattern::Custom { name: "big T".to_string(), mask: vec![vec![true]] }];
+        let mut log = EventLog::new();
+        log.push(Event::Drawn { number: 24 });
+        log.push(Event::Marked { board: 2, cells: vec![(0, 3), (4, 4)] });
+        log.push(Event::Won { board: 2, pattern: patterns[1].clone() });
+        let text = log.to_string();
+        assert_eq!("draw 24\nmark 2 0,3 4,4\nwin 2 big T\n", text);
+        assert_eq!(Ok(log), EventLog::parse(&text, &patterns));
+    }
+
+    #[test]
+    fn syntax_errors() {
+        let patterns = WinPattern::classic();
+        assert_eq!(Err(LogError::Syntax { line: 2, problem: "expected a number after draw" }),
+                   EventLog::parse("draw 1\ndraw x", &patterns));
+        assert_eq!(Err(LogError::Syntax { line: 1, problem: "expected cells as row,column after the board" }),
+                   EventLog::parse("mark 0 1;2", &patterns));
+        assert_eq!(Err(LogError::Syntax { line: 3, problem: "not a pattern of this game" }),
+                   EventLog::parse("draw 1\n\nwin 0 corners", &patterns));
+        assert_eq!(Err(LogError::Syntax { line: 1, problem: "expected draw, mark or win" }),
+                   EventLog::parse("undo", &patterns));
+    }
+}
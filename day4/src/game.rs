@@ -0,0 +1,386 @@
+//! A game played one draw at a time, recording what happens in an `EventLog`.
+//!
+//! Unlike `Bingo::simulate` the boards are really marked, so a game can be shown,
+//! stopped at any event and rebuilt from its log.
+
+use crate::events::{Event, EventLog, LogError};
+use crate::pattern::{Cell, LineCounter, LineLayout};
+use crate::{Bingo, Board, Finish};
+
+pub struct Game<'a> {
+    bingo: &'a Bingo,
+    boards: Vec<Board>,
+    layout: LineLayout,
+    counters: Vec<LineCounter>,
+    /// The first pattern a board completed on the current draw, until its win is logged.
+    pending: Vec<Option<usize>>,
+    won: Vec<Option<Finish>>,
+    /// How many numbers have been drawn.
+    drawn: usize,
+    log: EventLog,
+}
+
+impl<'a> Game<'a> {
+    /// The game before the first draw, every board unmarked.
+    pub fn new(bingo: &'a Bingo) -> Game<'a> {
+        let mut boards = bingo.boards.clone();
+        boards.iter_mut().for_each(Board::reset);
+        let (rows, columns) = boards.first().map_or((0, 0), |board| (board.rows(), board.columns()));
+        let layout = LineLayout::new(&bingo.patterns, rows, columns);
+        Game {
+            bingo,
+            counters: boards.iter().map(|_| LineCounter::new(&layout)).collect(),
+            pending: vec![None; boards.len()],
+            won: vec![None; boards.len()],
+            boards,
+            layout,
+            drawn: 0,
+            log: EventLog::new(),
+        }
+    }
+
+    /// The game after exactly `events`, which must be a possible course of it.
+    pub fn replayed(bingo: &'a Bingo, events: &[Event]) -> Result<Game<'a>, LogError> {
+        let mut game = Game::new(bingo);
+        for event in events {
+            game.apply(event)?;
+        }
+        Ok(game)
+    }
+
+    /// The game after the first `index` events of `log`, ready to draw on. A log cut
+    /// in the middle of a draw has that draw finished first.
+    pub fn resume(bingo: &'a Bingo, log: &EventLog, index: usize) -> Result<Game<'a>, LogError> {
+        if index > log.len() {
+            return Err(LogError::OutOfRange { index, len: log.len() });
+        }
+        let mut game = Game::replayed(bingo, &log.events()[..index])?;
+        game.settle();
+        Ok(game)
+    }
+
+    /// Checks that `event` can happen next and plays it.
+    pub fn apply(&mut self, event: &Event) -> Result<(), LogError> {
+        let diverges = |problem| LogError::Diverges { event: self.log.len(), problem };
+        match event {
+            Event::Drawn { number } => {
+                if self.pending.iter().any(Option::is_some) {
+                    return Err(diverges("a board completed a pattern without winning"));
+                }
+                if self.last_number().is_some_and(|last| self.boards.iter().any(|board| unmarked(board, last))) {
+                    return Err(diverges("the last number was not marked on every board"));
+                }
+                if self.is_over() {
+                    return Err(diverges("the game is over"));
+                }
+                if self.bingo.numbers.get(self.drawn) != Some(number) {
+                    return Err(diverges("not the next drawn number"));
+                }
+                self.drawn += 1;
+            }
+            Event::Marked { board, cells } => {
+                let number = self.last_number().ok_or(diverges("a mark before the first draw"))?;
+                let target = self.boards.get(*board).ok_or(diverges("no such board"))?;
+                for (k, &(i, j)) in cells.iter().enumerate() {
+                    if cells[..k].contains(&(i, j)) {
+                        return Err(diverges("the cell is marked twice"));
+                    }
+                    if i >= target.rows() || j >= target.columns() {
+                        return Err(diverges("a cell outside the board"));
+                    }
+                    if target.value(i, j) != number {
+                        return Err(diverges("the cell does not hold the drawn number"));
+                    }
+                    if target.is_drawn(i, j) {
+                        return Err(diverges("the cell is already marked"));
+                    }
+                }
+                cells.iter().for_each(|&cell| {
+                    self.boards[*board].draw(cell);
+                });
+                self.count(*board, cells);
+            }
+            Event::Won { board, pattern } => match self.pending.get(*board) {
+                Some(&Some(p)) if self.bingo.patterns[p] == *pattern => {
+                    self.pending[*board] = None;
+                    self.win(*board, p);
+                }
+                _ => return Err(diverges("the board did not just complete that pattern")),
+            },
+        }
+        self.log.push(event.clone());
+        Ok(())
+    }
+
+    /// Draws the next number. Returns the events it caused, or `None` once every
+    /// number is drawn or every board has won.
+    pub fn draw(&mut self) -> Option<&[Event]> {
+        if self.is_over() {
+            return None;
+        }
+        let start = self.log.len();
+        self.log.push(Event::Drawn { number: self.bingo.numbers[self.drawn] });
+        self.drawn += 1;
+        self.settle();
+        Some(&self.log.events()[start..])
+    }
+
+    /// Draws until the game is over.
+    pub fn play_out(&mut self) -> &EventLog {
+        while self.draw().is_some() {}
+        &self.log
+    }
+
+    /// Marks the last drawn number wherever it is not marked yet and logs the wins.
+    fn settle(&mut self) {
+        let Some(number) = self.last_number() else {
+            return;
+        };
+        for n in 0..self.boards.len() {
+            let cells = self.boards[n].mark(&number);
+            if !cells.is_empty() {
+                self.count(n, &cells);
+                self.log.push(Event::Marked { board: n, cells });
+            }
+            if let Some(p) = self.pending[n].take() {
+                self.win(n, p);
+                self.log.push(Event::Won { board: n, pattern: self.bingo.patterns[p].clone() });
+            }
+        }
+    }
+
+    /// Records that `board` won with pattern `p` on the last draw.
+    fn win(&mut self, board: usize, p: usize) {
+        let number = self.bingo.numbers[self.drawn - 1];
+        self.won[board] = Some(Finish {
+            board,
+            draw: self.drawn - 1,
+            number,
+            pattern: self.bingo.patterns[p].clone(),
+            score: self.boards[board].score(number),
+        });
+    }
+
+    /// Counts newly marked cells of a board towards its lines.
+    fn count(&mut self, board: usize, cells: &[Cell]) {
+        for &cell in cells {
+            if let Some(p) = self.counters[board].hit(&self.layout, cell) {
+                if self.won[board].is_none() {
+                    self.pending[board] = Some(self.pending[board].map_or(p, |q| q.min(p)));
+                }
+            }
+        }
+    }
+
+    pub fn is_over(&self) -> bool {
+        self.drawn == self.bingo.numbers.len() || self.won.iter().all(Option::is_some)
+    }
+
+    pub fn boards(&self) -> &[Board] {
+        &self.boards
+    }
+
+    /// The numbers drawn so far.
+    pub fn drawn(&self) -> &[u32] {
+        &self.bingo.numbers[..self.drawn]
+    }
+
+    pub fn last_number(&self) -> Option<u32> {
+        self.drawn().last().copied()
+    }
+
+    /// How `board` won, if it has.
+    pub fn won(&self, board: usize) -> Option<&Finish> {
+        self.won[board].as_ref()
+    }
+
+    pub fn log(&self) -> &EventLog {
+        &self.log
+    }
+
+    /// The drawn numbers and every board, drawn cells in brackets. Boards are
+    /// counted from 1 as in the rest of the output, unlike in the log.
+    pub fn render(&self) -> String {
+        let mut output = format!("Drawn {:?}\n", self.drawn());
+        for (n, board) in self.boards.iter().enumerate() {
+            match &self.won[n] {
+                Some(finish) => output.push_str(&format!("\nBoard {} won with {}\n", n + 1, finish.pattern)),
+                None => output.push_str(&format!("\nBoard {}\n", n + 1)),
+            }
+            output.push_str(&board.to_string());
+        }
+        output
+    }
+}
+
+/// Whether `number` is on `board` somewhere it is not marked.
+fn unmarked(board: &Board, number: u32) -> bool {
+    (0..board.rows()).any(|i| (0..board.columns()).any(|j| board.value(i, j) == number && !board.is_drawn(i, j)))
+}
+
+/// Steps back and forth through a recorded game.
+pub struct Replay<'a> {
+    log: EventLog,
+    game: Game<'a>,
+}
+
+impl<'a> Replay<'a> {
+    /// Starts before the first event. The whole log is checked against the game first.
+    pub fn new(bingo: &'a Bingo, log: EventLog) -> Result<Replay<'a>, LogError> {
+        Game::replayed(bingo, log.events())?;
+        Ok(Replay { log, game: Game::new(bingo) })
+    }
+
+    /// How many events have been played.
+    pub fn position(&self) -> usize {
+        self.game.log().len()
+    }
+
+    pub fn len(&self) -> usize {
+        self.log.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.log.is_empty()
+    }
+
+    /// Plays the next event, if there is one.
+    pub fn forward(&mut self) -> Option<&Event> {
+        let event = self.log.events().get(self.position())?;
+        self.game.apply(event).expect("the log was checked when the replay started");
+        Some(event)
+    }
+
+    /// Takes back the last event, if there is one.
+    pub fn back(&mut self) -> bool {
+        match self.position().checked_sub(1) {
+            Some(position) => self.goto(position).is_ok(),
+            None => false,
+        }
+    }
+
+    /// Jumps to the game after the first `position` events.
+    pub fn goto(&mut self, position: usize) -> Result<(), LogError> {
+        if position > self.log.len() {
+            return Err(LogError::OutOfRange { index: position, len: self.log.len() });
+        }
+        self.game = Game::replayed(self.game.bingo, &self.log.events()[..position])?;
+        Ok(())
+    }
+
+    pub fn game(&self) -> &Game<'a> {
+        &self.game
+    }
+
+    /// The current event and the game after it.
+    pub fn render(&self) -> String {
+        let last = match self.position().checked_sub(1) {
+            Some(i) => self.log.events()[i].to_string(),
+            None => "start".to_string(),
+        };
+        format!("Event {}/{}: {}\n{}", self.position(), self.len(), last, self.game.render())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::GAME;
+    use crate::pattern::WinPattern;
+    use crate::Strategy;
+
+    fn winners(log: &EventLog) -> Vec<(usize, WinPattern)> {
+        log.events()
+            .iter()
+            .filter_map(|event| match event {
+                Event::Won { board, pattern } => Some((*board, pattern.clone())),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn log_agrees_with_simulation() {
+        let bingo = Bingo::builder(GAME).unwrap();
+        let mut game = Game::new(&bingo);
+        let log = game.play_out().clone();
+        let ranking = bingo.simulate();
+        let expected: Vec<_> = ranking.finishes.iter().map(|finish| (finish.board, finish.pattern.clone())).collect();
+        assert_eq!(expected, winners(&log));
+        for finish in &ranking.finishes {
+            assert_eq!(Some(finish), game.won(finish.board));
+        }
+        assert_eq!(Some(13), game.last_number());
+        let lose = bingo.play(Strategy::Lose).unwrap();
+        assert_eq!(lose.score, game.boards()[lose.board].score(lose.number));
+        assert_eq!(&[Event::Drawn { number: 7 }, Event::Marked { board: 0, cells: vec![(2, 4)] }], &log.events()[..2]);
+        // the parsed game is left alone
+        assert!(!bingo.boards()[0].is_drawn(2, 4));
+    }
+
+    #[test]
+    fn resume_anywhere() {
+        let bingo = Bingo::builder(GAME).unwrap();
+        let log = Game::new(&bingo).play_out().clone();
+        for index in 0..=log.len() {
+            let mut game = Game::resume(&bingo, &log, index).unwrap();
+            game.play_out();
+            assert_eq!(&log, game.log(), "resumed at {}", index);
+        }
+        // cut after a mark that completed a row, before its win
+        let won = log.events().iter().position(|event| matches!(event, Event::Won { .. })).unwrap();
+        let game = Game::resume(&bingo, &log, won).unwrap();
+        assert_eq!(&log.events()[..=won], game.log().events());
+        assert_eq!(Err(LogError::OutOfRange { index: log.len() + 1, len: log.len() }),
+                   Game::resume(&bingo, &log, log.len() + 1).map(|_| ()));
+    }
+
+    #[test]
+    fn rejects_diverging_logs() {
+        let bingo = Bingo::builder(GAME).unwrap();
+        let parse = |s| EventLog::parse(s, &bingo.patterns).unwrap();
+        let problem = |s| Game::replayed(&bingo, parse(s).events()).map(|_| ()).unwrap_err();
+        assert_eq!(LogError::Diverges { event: 0, problem: "not the next drawn number" }, problem("draw 4"));
+        assert_eq!(LogError::Diverges { event: 0, problem: "a mark before the first draw" }, problem("mark 0 2,4"));
+        assert_eq!(LogError::Diverges { event: 1, problem: "the cell does not hold the drawn number" },
+                   problem("draw 7\nmark 0 0,0"));
+        assert_eq!(LogError::Diverges { event: 2, problem: "the cell is already marked" },
+                   problem("draw 7\nmark 0 2,4\nmark 0 2,4"));
+        assert_eq!(LogError::Diverges { event: 1, problem: "the cell is marked twice" }, problem("draw 7\nmark 0 2,4 2,4"));
+        assert_eq!(LogError::Diverges { event: 1, problem: "the last number was not marked on every board" }, problem("draw 7\ndraw 4"));
+        assert_eq!(LogError::Diverges { event: 2, problem: "the last number was not marked on every board" },
+                   problem("draw 7\nmark 0 2,4\ndraw 4"));
+        assert_eq!(LogError::Diverges { event: 1, problem: "no such board" }, problem("draw 7\nmark 3 2,4"));
+        assert_eq!(LogError::Diverges { event: 1, problem: "the board did not just complete that pattern" },
+                   problem("draw 7\nwin 0 rows"));
+        // every board has won, so the next number is not drawn
+        let mut log = Game::new(&bingo).play_out().clone();
+        let drawn = log.events().iter().filter(|event| matches!(event, Event::Drawn { .. })).count();
+        let event = log.events().len();
+        log.push(Event::Drawn { number: bingo.numbers()[drawn] });
+        assert_eq!(Some(LogError::Diverges { event, problem: "the game is over" }), Game::replayed(&bingo, log.events()).err());
+    }
+
+    #[test]
+    fn replay_steps_both_ways() {
+        let bingo = Bingo::builder(GAME).unwrap();
+        let log = Game::new(&bingo).play_out().clone();
+        let mut replay = Replay::new(&bingo, log.clone()).unwrap();
+        assert!(!replay.back());
+        assert_eq!(Some(&Event::Drawn { number: 7 }), replay.forward());
+        replay.forward();
+        assert!(replay.game().boards()[0].is_drawn(2, 4));
+        assert!(replay.back());
+        assert_eq!(1, replay.position());
+        assert!(!replay.game().boards()[0].is_drawn(2, 4));
+
+        replay.goto(log.len()).unwrap();
+        assert_eq!(None, replay.forward());
+        assert!(replay.render().starts_with(&format!("Event {}/{}: mark 2 3,2\nDrawn [7, 4", log.len(), log.len())));
+        assert!(replay.render().contains("Board 3 won with rows\n[14] [21] [17] [24] [ 4]\n"));
+
+        let mut extended = log.clone();
+        extended.push(Event::Drawn { number: 99 });
+        assert!(Replay::new(&bingo, extended).is_err());
+    }
+}
@@ -0,0 +1,221 @@
+//! Random games in the input format, with guarantees checked by playing them.
+//!
+//! The numbers are drawn without repeats from a range. Decks are generated until
+//! one has the required properties; a chosen winner is put in place by swapping
+//! the board that finishes in that place with the chosen one, so only ties for the
+//! place make a deck fail.
+
+use std::collections::HashSet;
+use std::fmt::Formatter;
+use std::ops::RangeInclusive;
+
+use crate::rng::Rng;
+use crate::{Bingo, Strategy};
+
+/// Decks tried before giving up.
+const ATTEMPTS: u64 = 1000;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Spec {
+    pub boards: usize,
+    pub rows: usize,
+    pub columns: usize,
+    pub range: RangeInclusive<u32>,
+    /// How many numbers are drawn, all of the range if `None`.
+    pub draws: Option<usize>,
+    /// No number twice on a board.
+    pub unique: bool,
+    /// Every board wins before the numbers run out.
+    pub winnable: bool,
+    /// The board that must finish in the place of the strategy, alone.
+    pub winner: Option<(usize, Strategy)>,
+    pub seed: u64,
+}
+
+impl Default for Spec {
+    /// The size of the puzzle input.
+    fn default() -> Spec {
+        Spec { boards: 100, rows: 5, columns: 5, range: 0..=99, draws: None, unique: false, winnable: false, winner: None, seed: 1 }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum GenerateError {
+    /// No numbers to draw, or no board of at least one cell.
+    Empty,
+    /// Fewer numbers in the range than a unique board or the draws need.
+    RangeTooSmall { needed: u64, size: u64 },
+    NoSuchBoard { board: usize, boards: usize },
+    GaveUp { attempts: u64 },
+}
+
+impl std::fmt::Display for GenerateError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GenerateError::Empty => write!(f, "a deck needs numbers to draw and at least one board of at least one cell"),
+            GenerateError::RangeTooSmall { needed, size } => write!(f, "{} distinct numbers are needed, the range has {}", needed, size),
+            GenerateError::NoSuchBoard { board, boards } => write!(f, "board {} was chosen to win, there are {}", board + 1, boards),
+            GenerateError::GaveUp { attempts } => write!(f, "no deck had the required properties after {} attempts", attempts),
+        }
+    }
+}
+
+impl std::error::Error for GenerateError {}
+
+/// A deck in the input format that `Bingo::play` has confirmed meets `spec`.
+pub fn generate(spec: &Spec) -> Result<String, GenerateError> {
+    let cells = spec.rows * spec.columns;
+    if spec.boards == 0 || cells == 0 || spec.draws == Some(0) {
+        return Err(GenerateError::Empty);
+    }
+    let size = match spec.range.is_empty() {
+        true => 0,
+        false => u64::from(*spec.range.end()) - u64::from(*spec.range.start()) + 1,
+    };
+    if size == 0 {
+        return Err(GenerateError::Empty);
+    }
+    let draws = spec.draws.map_or(size, |draws| draws as u64);
+    let needed = if spec.unique { draws.max(cells as u64) } else { draws };
+    if needed > size {
+        return Err(GenerateError::RangeTooSmall { needed, size });
+    }
+    if let Some((board, _)) = spec.winner.filter(|&(board, _)| board >= spec.boards) {
+        return Err(GenerateError::NoSuchBoard { board, boards: spec.boards });
+    }
+
+    for attempt in 0..ATTEMPTS {
+        let mut rng = Rng::stream(spec.seed, attempt);
+        let numbers = sample(&mut rng, &spec.range, draws as usize, true);
+        let mut boards: Vec<Vec<u32>> = (0..spec.boards).map(|_| sample(&mut rng, &spec.range, cells, spec.unique)).collect();
+        let bingo = Bingo::builder(&format(spec, &numbers, &boards)).expect("generated decks are well formed");
+        let ranking = bingo.simulate();
+        if spec.winnable && !ranking.never.is_empty() {
+            continue;
+        }
+        let Some((target, strategy)) = spec.winner else {
+            // there is a last board to win only if every board wins
+            if !spec.winnable || bingo.play(Strategy::Lose).is_ok() {
+                return Ok(format(spec, &numbers, &boards));
+            }
+            continue;
+        };
+        let Some(finish) = ranking.query(strategy) else {
+            continue;
+        };
+        if ranking.finishes.iter().filter(|other| other.draw == finish.draw).count() > 1 {
+            continue;
+        }
+        boards.swap(target, finish.board);
+        let deck = format(spec, &numbers, &boards);
+        let bingo = Bingo::builder(&deck).expect("generated decks are well formed");
+        if bingo.play(strategy).is_ok_and(|finish| finish.board == target) {
+            return Ok(deck);
+        }
+    }
+    Err(GenerateError::GaveUp { attempts: ATTEMPTS })
+}
+
+/// `count` numbers from `range`, `distinct` ones if asked.
+fn sample(rng: &mut Rng, range: &RangeInclusive<u32>, count: usize, distinct: bool) -> Vec<u32> {
+    let start = *range.start();
+    let size = u64::from(*range.end()) - u64::from(start) + 1;
+    let pick = |rng: &mut Rng| start + rng.below(size) as u32;
+    if !distinct {
+        return (0..count).map(|_| pick(rng)).collect();
+    }
+    // most of the range is needed: shuffle all of it
+    if count as u64 * 2 > size {
+        let mut all: Vec<u32> = range.clone().collect();
+        rng.shuffle(&mut all);
+        all.truncate(count);
+        return all;
+    }
+    let mut seen = HashSet::new();
+    let mut numbers = Vec::with_capacity(count);
+    while numbers.len() < count {
+        let number = pick(rng);
+        if seen.insert(number) {
+            numbers.push(number);
+        }
+    }
+    numbers
+}
+
+/// The input format, board numbers aligned.
+fn format(spec: &Spec, numbers: &[u32], boards: &[Vec<u32>]) -> String {
+    let width = spec.range.end().to_string().len();
+    let mut deck = numbers.iter().map(u32::to_string).collect::<Vec<_>>().join(",");
+    deck.push('\n');
+    for board in boards {
+        deck.push('\n');
+        for row in board.chunks(spec.columns) {
+            let row: Vec<String> = row.iter().map(|n| format!("{:>width$}", n)).collect();
+            deck.push_str(&row.join(" "));
+            deck.push('\n');
+        }
+    }
+    deck
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn puzzle_sized_deck() {
+        let deck = generate(&Spec::default()).unwrap();
+        assert_eq!(deck, generate(&Spec::default()).unwrap());
+        assert_ne!(deck, generate(&Spec { seed: 2, ..Spec::default() }).unwrap());
+        let bingo = Bingo::builder(&deck).unwrap();
+        assert_eq!(100, bingo.boards().len());
+        let mut numbers = bingo.numbers().to_vec();
+        numbers.sort();
+        assert_eq!((0..100).collect::<Vec<_>>(), numbers);
+        assert!(crate::lint::lint(&deck, None).iter().all(|issue| !issue.is_error()));
+    }
+
+    #[test]
+    fn unique_and_winnable() {
+        let spec = Spec { boards: 20, rows: 3, columns: 4, range: 1..=40, draws: Some(30), unique: true, winnable: true, seed: 9, ..Spec::default() };
+        let bingo = Bingo::builder(&generate(&spec).unwrap()).unwrap();
+        assert_eq!(30, bingo.numbers().len());
+        for board in bingo.boards() {
+            assert_eq!((3, 4), (board.rows(), board.columns()));
+            let mut cells: Vec<u32> = (0..3).flat_map(|i| (0..4).map(move |j| board.value(i, j))).collect();
+            assert!(cells.iter().all(|n| (1..=40).contains(n)));
+            cells.sort();
+            cells.dedup();
+            assert_eq!(12, cells.len());
+        }
+        assert!(bingo.simulate().never.is_empty());
+    }
+
+    #[test]
+    fn chosen_winners() {
+        for (board, strategy) in [(0, Strategy::Win), (7, Strategy::Win), (3, Strategy::Lose), (5, Strategy::Nth(2))] {
+            let spec = Spec { boards: 10, winner: Some((board, strategy)), seed: board as u64, ..Spec::default() };
+            let bingo = Bingo::builder(&generate(&spec).unwrap()).unwrap();
+            let ranking = bingo.simulate();
+            let finish = ranking.query(strategy).unwrap();
+            assert_eq!(board, finish.board);
+            assert_eq!(1, ranking.finishes.iter().filter(|other| other.draw == finish.draw).count());
+        }
+    }
+
+    #[test]
+    fn impossible_specs() {
+        let spec = Spec { range: 1..=10, unique: true, draws: Some(5), ..Spec::default() };
+        assert_eq!(Err(GenerateError::RangeTooSmall { needed: 25, size: 10 }), generate(&spec));
+        let spec = Spec { range: 1..=10, draws: Some(11), ..Spec::default() };
+        assert_eq!(Err(GenerateError::RangeTooSmall { needed: 11, size: 10 }), generate(&spec));
+        assert_eq!(Err(GenerateError::NoSuchBoard { board: 3, boards: 3 }),
+                   generate(&Spec { boards: 3, winner: Some((3, Strategy::Win)), ..Spec::default() }));
+        assert_eq!(Err(GenerateError::Empty), generate(&Spec { boards: 0, ..Spec::default() }));
+        assert_eq!(Err(GenerateError::Empty), generate(&Spec { draws: Some(0), ..Spec::default() }));
+        assert_eq!(Err(GenerateError::Empty), generate(&Spec { range: RangeInclusive::new(5, 3), ..Spec::default() }));
+        // two draws cannot complete a row of five
+        let spec = Spec { boards: 2, draws: Some(2), winnable: true, ..Spec::default() };
+        assert_eq!(Err(GenerateError::GaveUp { attempts: ATTEMPTS }), generate(&spec));
+    }
+}
@@ -1,51 +1,429 @@
+use std::collections::HashMap;
 use std::error::Error;
 use std::fmt::Formatter;
+use std::env;
 use std::fs;
+use std::io::{self, BufRead, Write};
+use std::num::{NonZeroU64, NonZeroUsize};
+use std::thread;
+use std::time::Duration;
+
+use events::EventLog;
+use game::{Game, Replay};
+use pattern::{Cell, LineCounter, LineLayout, WinPattern};
+
+pub mod events;
+#[cfg(test)]
+mod fixtures;
+pub mod game;
+pub mod generate;
+pub mod lint;
+pub mod net;
+pub mod odds;
+pub mod pattern;
+pub mod rng;
+pub mod solver;
+pub mod terminal;
 
 pub struct Config {
-    pub file_path: String,
+    pub task: Task,
+    /// Built-in patterns to play with instead of rows and columns.
+    pub patterns: Option<Vec<WinPattern>>,
+    /// Also report the board finishing in this place, counted from 1.
+    pub nth: Option<usize>,
+    /// Write the event log of the game to this file.
+    pub log: Option<String>,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Task {
+    /// Find the first and last winning boards.
+    Solve { file_path: String },
+    /// Step through the event log of a game.
+    Replay { file_path: String, log_path: String },
+    /// Estimate how likely each board is to win first and last.
+    Odds { file_path: String, trials: u64, seed: u64, threads: Option<usize> },
+    /// Only check the input for problems.
+    Lint { file_path: String },
+    /// Play the game interactively in the terminal.
+    Play { file_path: String },
+    /// Find the shortest draw order that makes a board win first, or last.
+    Force { file_path: String, board: usize, goal: solver::Goal, limit: Duration },
+    /// Host the game for players over TCP, drawing on a timer or when told to.
+    Serve { file_path: String, addr: String, interval: Option<Duration> },
+    /// Join a game hosted with `Serve`.
+    Client { addr: String, auto: bool },
+    /// Write a random game to a file, played on rows and columns.
+    Generate { file_path: String, spec: generate::Spec },
 }
 
 impl Config {
+    /// Reads `day4 [task] <arguments> [options]`. Anything that is not a task is the
+    /// game file to solve; a file named like a task is solved with `--` before it, or
+    /// as `./<name>`.
     pub fn build(
         mut args: impl Iterator<Item=String>,
-    ) -> Result<Config, &'static str> {
+    ) -> Result<Config, String> {
         args.next();
 
-        let file_path = match args.next() {
-            Some(arg) => arg,
-            None => return Err("Didn't get a file path"),
+        let args = &mut args;
+        let mut options = Options::default();
+        let task = match args.next().as_deref() {
+            Some("replay") => parse_replay(args, &mut options)?,
+            Some("lint") => parse_lint(args, &mut options)?,
+            Some("play") => parse_play(args, &mut options)?,
+            Some("serve") => parse_serve(args, &mut options)?,
+            Some("client") => parse_client(args)?,
+            Some("generate") => parse_generate(args)?,
+            Some("odds") => parse_odds(args, &mut options)?,
+            Some("force") => parse_force(args, &mut options)?,
+            Some("--") => parse_solve(args.next().ok_or("Expected a game file after --")?, args, &mut options)?,
+            Some(arg) => parse_solve(arg.to_string(), args, &mut options)?,
+            None => return Err("Didn't get a file path".to_string()),
         };
 
         Ok(Config {
-            file_path,
+            task,
+            patterns: options.patterns,
+            nth: options.nth,
+            log: options.log,
         })
     }
 }
 
+/// The options of `Config` that are not part of a task.
+#[derive(Default)]
+struct Options {
+    patterns: Option<Vec<WinPattern>>,
+    nth: Option<usize>,
+    log: Option<String>,
+}
+
+impl Options {
+    /// Reads `--patterns`, which every task playing a game file takes. Any other
+    /// option is unknown to `task`.
+    fn patterns(&mut self, flag: &str, args: &mut impl Iterator<Item=String>, task: &str) -> Result<(), String> {
+        if flag != "--patterns" {
+            return Err(unknown(flag, task));
+        }
+        let names = args.next().ok_or("Expected a comma separated list of patterns after --patterns")?;
+        self.patterns = Some(
+            names
+                .split(',')
+                .map(WinPattern::by_name)
+                .collect::<Option<Vec<_>>>()
+                .ok_or("Unknown pattern, expected rows, columns, diagonals, corners, blackout or x")?,
+        );
+        Ok(())
+    }
+}
+
+fn unknown(flag: &str, task: &str) -> String {
+    format!("unknown option '{}' for {}", flag, task)
+}
+
+/// The argument after `flag`, parsed as `expected`.
+fn value<T: std::str::FromStr>(args: &mut impl Iterator<Item=String>, flag: &str, expected: &str) -> Result<T, String> {
+    args.next().and_then(|value| value.parse().ok()).ok_or_else(|| format!("Expected {} after {}", expected, flag))
+}
+
+fn parse_solve(file_path: String, args: &mut impl Iterator<Item=String>, options: &mut Options) -> Result<Task, String> {
+    while let Some(flag) = args.next() {
+        match flag.as_str() {
+            "--nth" => options.nth = Some(value(args, &flag, "a place")?),
+            "--log" => options.log = Some(value(args, &flag, "a file path")?),
+            _ => options.patterns(&flag, args, "solve")?,
+        }
+    }
+    Ok(Task::Solve { file_path })
+}
+
+fn parse_replay(args: &mut impl Iterator<Item=String>, options: &mut Options) -> Result<Task, String> {
+    let (Some(file_path), Some(log_path)) = (args.next(), args.next()) else {
+        return Err("Expected a game file and a log file after replay".to_string());
+    };
+    while let Some(flag) = args.next() {
+        options.patterns(&flag, args, "replay")?;
+    }
+    Ok(Task::Replay { file_path, log_path })
+}
+
+fn parse_lint(args: &mut impl Iterator<Item=String>, options: &mut Options) -> Result<Task, String> {
+    let file_path = args.next().ok_or("Expected a game file after lint")?;
+    while let Some(flag) = args.next() {
+        options.patterns(&flag, args, "lint")?;
+    }
+    Ok(Task::Lint { file_path })
+}
+
+fn parse_play(args: &mut impl Iterator<Item=String>, options: &mut Options) -> Result<Task, String> {
+    let file_path = args.next().ok_or("Expected a game file after play")?;
+    while let Some(flag) = args.next() {
+        options.patterns(&flag, args, "play")?;
+    }
+    Ok(Task::Play { file_path })
+}
+
+fn parse_serve(args: &mut impl Iterator<Item=String>, options: &mut Options) -> Result<Task, String> {
+    let (Some(file_path), Some(addr)) = (args.next(), args.next()) else {
+        return Err("Expected a game file and an address to listen on after serve".to_string());
+    };
+    let mut interval = None;
+    while let Some(flag) = args.next() {
+        match flag.as_str() {
+            "--interval" => interval = Some(Duration::from_millis(value(args, &flag, "milliseconds")?)),
+            _ => options.patterns(&flag, args, "serve")?,
+        }
+    }
+    Ok(Task::Serve { file_path, addr, interval })
+}
+
+fn parse_client(args: &mut impl Iterator<Item=String>) -> Result<Task, String> {
+    let addr = args.next().ok_or("Expected the address of a server after client")?;
+    let mut auto = false;
+    for flag in args {
+        match flag.as_str() {
+            "--auto" => auto = true,
+            _ => return Err(unknown(&flag, "client")),
+        }
+    }
+    Ok(Task::Client { addr, auto })
+}
+
+/// Generated games are played on rows and columns, so `--patterns` is not taken.
+fn parse_generate(args: &mut impl Iterator<Item=String>) -> Result<Task, String> {
+    let file_path = args.next().ok_or("Expected a file to write after generate")?;
+    let mut spec = generate::Spec::default();
+    while let Some(flag) = args.next() {
+        let pair = |value: &str, separator: char| -> Option<(u32, u32)> {
+            let (a, b) = value.split_once(separator)?;
+            Some((a.parse().ok()?, b.parse().ok()?))
+        };
+        // counted from 1 on the command line
+        let board = |k: NonZeroUsize| k.get() - 1;
+        match flag.as_str() {
+            "--unique" => spec.unique = true,
+            "--winnable" => spec.winnable = true,
+            "--boards" => spec.boards = value(args, &flag, "a number of boards")?,
+            "--size" => {
+                let size: String = value(args, &flag, "rows x columns, like 5x5,")?;
+                let (rows, columns) = pair(&size, 'x').ok_or("Expected rows x columns, like 5x5, after --size")?;
+                (spec.rows, spec.columns) = (rows as usize, columns as usize);
+            }
+            "--range" => {
+                let range: String = value(args, &flag, "the lowest and highest number, like 0-99,")?;
+                match pair(&range, '-') {
+                    Some((low, high)) if low <= high => spec.range = low..=high,
+                    _ => return Err("Expected the lowest and highest number, like 0-99, after --range".to_string()),
+                }
+            }
+            "--draws" => spec.draws = Some(value(args, &flag, "a number of draws")?),
+            "--seed" => spec.seed = value(args, &flag, "a number")?,
+            "--wins-first" => spec.winner = Some((board(value(args, &flag, "a board, counted from 1,")?), Strategy::Win)),
+            "--wins-last" => spec.winner = Some((board(value(args, &flag, "a board, counted from 1,")?), Strategy::Lose)),
+            _ => return Err(unknown(&flag, "generate")),
+        }
+    }
+    Ok(Task::Generate { file_path, spec })
+}
+
+fn parse_odds(args: &mut impl Iterator<Item=String>, options: &mut Options) -> Result<Task, String> {
+    let file_path = args.next().ok_or("Expected a game file after odds")?;
+    let (mut trials, mut seed, mut threads) = (10_000, 1, None);
+    while let Some(flag) = args.next() {
+        match flag.as_str() {
+            "--trials" => trials = value::<NonZeroU64>(args, &flag, "a positive number")?.get(),
+            "--seed" => seed = value(args, &flag, "a number")?,
+            "--threads" => threads = Some(value::<NonZeroUsize>(args, &flag, "a positive number")?.get()),
+            _ => options.patterns(&flag, args, "odds")?,
+        }
+    }
+    Ok(Task::Odds { file_path, trials, seed, threads })
+}
+
+fn parse_force(args: &mut impl Iterator<Item=String>, options: &mut Options) -> Result<Task, String> {
+    let (Some(file_path), Some(board)) = (args.next(), args.next().and_then(|n| n.parse::<NonZeroUsize>().ok())) else {
+        return Err("Expected a game file and a board, counted from 1, after force".to_string());
+    };
+    let (mut goal, mut limit) = (solver::Goal::WinFirst, Duration::from_secs(10));
+    while let Some(flag) = args.next() {
+        match flag.as_str() {
+            "--last" => goal = solver::Goal::LoseLast,
+            "--seconds" => {
+                let seconds: f64 = value(args, &flag, "a number of seconds")?;
+                limit = Duration::try_from_secs_f64(seconds).map_err(|_| "Expected a number of seconds after --seconds")?;
+            }
+            _ => options.patterns(&flag, args, "force")?,
+        }
+    }
+    Ok(Task::Force { file_path, board: board.get() - 1, goal, limit })
+}
+
+#[derive(Debug, PartialEq)]
+pub enum BingoError {
+    NoNumbers,
+    NoBoards,
+    BadNumber { line: usize, found: String },
+    /// A board row with a different number of cells than the rows above it.
+    Ragged { line: usize, columns: usize, expected: usize },
+    /// A board with different dimensions than the first board of the game.
+    Mismatched { line: usize, rows: usize, columns: usize, expected_rows: usize, expected_columns: usize },
+    BadPattern { line: usize, problem: &'static str },
+    /// A custom pattern with different dimensions than the boards.
+    PatternSize { line: usize, rows: usize, columns: usize, expected_rows: usize, expected_columns: usize },
+}
+
+impl std::fmt::Display for BingoError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BingoError::NoNumbers => write!(f, "the input has no drawn numbers"),
+            BingoError::NoBoards => write!(f, "the input has no boards"),
+            BingoError::BadNumber { line, found } => write!(f, "line {}: '{}' is not a number", line, found),
+            BingoError::Ragged { line, columns, expected } => {
+                write!(f, "line {} has {} numbers, the rows above have {}", line, columns, expected)
+            }
+            BingoError::Mismatched { line, rows, columns, expected_rows, expected_columns } => write!(
+                f,
+                "the board at line {} is {}x{}, the first board is {}x{}",
+                line, rows, columns, expected_rows, expected_columns
+            ),
+            BingoError::BadPattern { line, problem } => write!(f, "line {}: {}", line, problem),
+            BingoError::PatternSize { line, rows, columns, expected_rows, expected_columns } => write!(
+                f,
+                "the pattern at line {} is {}x{}, the boards are {}x{}",
+                line, rows, columns, expected_rows, expected_columns
+            ),
+        }
+    }
+}
+
+impl Error for BingoError {}
+
+impl BingoError {
+    /// The input line the error is on, if it is about one.
+    pub fn line(&self) -> Option<usize> {
+        match self {
+            BingoError::NoNumbers | BingoError::NoBoards => None,
+            BingoError::BadNumber { line, .. }
+            | BingoError::Ragged { line, .. }
+            | BingoError::Mismatched { line, .. }
+            | BingoError::BadPattern { line, .. }
+            | BingoError::PatternSize { line, .. } => Some(*line),
+        }
+    }
+}
+
+fn parse_number(line: usize, s: &str) -> Result<u32, BingoError> {
+    s.trim().parse().map_err(|_| BingoError::BadNumber { line, found: s.trim().to_string() })
+}
+
+/// The drawn numbers on `line`, separated by commas.
+fn drawn_numbers(line: usize, s: &str) -> impl Iterator<Item = Result<u32, BingoError>> + '_ {
+    s.split(',').map(move |x| parse_number(line, x))
+}
+
+/// The cells of a board row on `line`, separated by whitespace.
+fn row_numbers(line: usize, s: &str) -> impl Iterator<Item = Result<u32, BingoError>> + '_ {
+    s.split_whitespace().map(move |x| parse_number(line, x))
+}
+
+/// An input file in its parts, each line with its number in the input. Shared by
+/// `Bingo::builder` and the linter, so both read a file the same way.
+struct Sections<'a> {
+    /// The first line, unless it is missing or blank.
+    numbers: Option<(usize, &'a str)>,
+    /// The runs of lines between blank lines after it: boards, and patterns where
+    /// `is_pattern`.
+    blocks: Vec<Vec<(usize, &'a str)>>,
+}
+
+impl Sections<'_> {
+    fn new(s: &str) -> Sections<'_> {
+        let mut lines = s.lines().enumerate().map(|(i, line)| (i + 1, line));
+        let numbers = lines.next().filter(|(_, line)| !line.trim().is_empty());
+        let mut blocks = vec![];
+        let mut block = vec![];
+        for (line_no, line) in lines {
+            if !line.trim().is_empty() {
+                block.push((line_no, line));
+            } else if !block.is_empty() {
+                blocks.push(std::mem::take(&mut block));
+            }
+        }
+        if !block.is_empty() {
+            blocks.push(block);
+        }
+        Sections { numbers, blocks }
+    }
+}
+
+/// Whether a block of lines is a custom pattern rather than a board.
+fn is_pattern(block: &[(usize, &str)]) -> bool {
+    block[0].1.trim_start().starts_with("pattern")
+}
+
 struct Submarine {
     bingo: Bingo,
 }
 
 impl Submarine {
-    fn new() -> Submarine {
-        Submarine { bingo: Bingo::new() }
-    }
-    fn builder(s:&str) -> Submarine {
-        Submarine{ bingo: Bingo::builder(s)}
+    fn builder(s: &str) -> Result<Submarine, BingoError> {
+        Ok(Submarine { bingo: Bingo::builder(s)? })
     }
 }
 
-enum Strategy {
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Strategy {
     Win,
+    /// The last board to win, if every board does.
     Lose,
+    /// The board finishing in this place, counted from 1.
+    Nth(usize),
+}
+
+/// How and when one board won.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Finish {
+    pub board: usize,
+    /// Index of the winning draw in the drawn numbers.
+    pub draw: usize,
+    pub number: u32,
+    pub pattern: WinPattern,
+    /// Sum of the unmarked numbers times the winning number.
+    pub score: u64,
+}
+
+/// Every board in the order it won, boards winning on the same draw in board order.
+#[derive(Debug, PartialEq)]
+pub struct Ranking {
+    pub finishes: Vec<Finish>,
+    /// Boards that are still without bingo once every number is drawn.
+    pub never: Vec<usize>,
+}
+
+impl Ranking {
+    pub fn query(&self, strategy: Strategy) -> Option<&Finish> {
+        match strategy {
+            Strategy::Win => self.finishes.first(),
+            Strategy::Lose if self.never.is_empty() => self.finishes.last(),
+            Strategy::Lose => None,
+            Strategy::Nth(place) => self.finishes.get(place.checked_sub(1)?),
+        }
+    }
+
+    /// The place of `board`, counted from 1, and how it won.
+    pub fn of(&self, board: usize) -> Option<(usize, &Finish)> {
+        self.finishes.iter().enumerate().find(|(_, finish)| finish.board == board).map(|(i, finish)| (i + 1, finish))
+    }
 }
 
-struct Bingo {
+pub struct Bingo {
     numbers: Vec<u32>,
     boards: Vec<Board>,
-    winning_number: u32,
-    winning_board: Board,
+    /// A board wins by completing any of these.
+    patterns: Vec<WinPattern>,
+    /// The board and cell of every occurrence of each number, in board order.
+    index: HashMap<u32, Vec<(usize, Cell)>>,
 }
 
 impl Bingo {
@@ -53,131 +431,253 @@ impl Bingo {
         Bingo {
             numbers: vec![],
             boards: vec![],
-            winning_number: 0,
-            winning_board: Board::new(),
+            patterns: WinPattern::classic(),
+            index: HashMap::new(),
         }
     }
 
-    fn builder(s: &str) -> Bingo {
+    /// The drawn numbers on the first line, then boards and custom patterns separated
+    /// by blank lines. All boards and patterns must have the same dimensions.
+    pub fn builder(s: &str) -> Result<Bingo, BingoError> {
         let mut bingo = Bingo::new();
-        let (numbers, boards) = s.split_once("\n\n").expect("no newline");
-        bingo.numbers = numbers.split(',').map(|x| x.parse().expect("")).collect::<Vec<_>>();
-        for board in boards.split("\n\n") {
-            bingo.boards.push(Board::builder(board));
-        }
-        bingo
-    }
-
-    fn play(&mut self, strat: Strategy) -> Result<&Bingo, &str> {
-        let mut winners:Vec<usize> = vec![];
-        let num_of_boards = self.boards.len();
-        for number in self.numbers.iter() {
-            for (n, board) in self.boards.iter_mut().enumerate() {
-                board.mark(number);
-                if board.has_bingo() {
-                    match strat {
-                        Strategy::Win => {
-                            self.winning_number = *number;
-                            self.winning_board = *board;
-                            return Ok(self);
-                        }
-                        Strategy::Lose => {
-                            if !winners.contains(&n) {
-                                winners.push(n);
-                                if winners.len() >= num_of_boards {
-                                    self.winning_number = *number;
-                                    self.winning_board = *board;
-                                    return Ok(self);
-                                }
-                            }
-
-                        }
-                    }
+        let sections = Sections::new(s);
+        let (line_no, numbers) = sections.numbers.ok_or(BingoError::NoNumbers)?;
+        bingo.numbers = drawn_numbers(line_no, numbers).collect::<Result<Vec<_>, _>>()?;
+
+        let mut patterns = vec![];
+        for board in &sections.blocks {
+            if is_pattern(board) {
+                patterns.push((board[0].0, WinPattern::parse(board)?));
+                continue;
+            }
+            let next = Board::from_lines(board)?;
+            if let Some(first) = bingo.boards.first() {
+                if (next.rows(), next.columns()) != (first.rows(), first.columns()) {
+                    return Err(BingoError::Mismatched {
+                        line: board[0].0,
+                        rows: next.rows(),
+                        columns: next.columns(),
+                        expected_rows: first.rows(),
+                        expected_columns: first.columns(),
+                    });
                 }
             }
+            for (&number, cells) in &next.index {
+                let occurrences = bingo.index.entry(number).or_default();
+                occurrences.extend(cells.iter().map(|&cell| (bingo.boards.len(), cell)));
+            }
+            bingo.boards.push(next);
+        }
+        let first = bingo.boards.first().ok_or(BingoError::NoBoards)?;
+        let (expected_rows, expected_columns) = (first.rows(), first.columns());
+        for (line, pattern) in patterns {
+            if let Some((rows, columns)) = pattern.size().filter(|&size| size != (expected_rows, expected_columns)) {
+                return Err(BingoError::PatternSize { line, rows, columns, expected_rows, expected_columns });
+            }
+            bingo.patterns.push(pattern);
         }
-        Err("could not find a winning board")
+        Ok(bingo)
     }
 
-    fn get(&self) -> u32 {
-        let mut result = 0;
-        for field in self.winning_board.fields.iter().flat_map(|r| r.iter()) {
-            if !field.drawn {
-                result += field.value;
+    /// Plays with `patterns` instead of rows and columns. Custom patterns from the
+    /// input stay.
+    pub fn with_patterns(mut self, patterns: Vec<WinPattern>) -> Bingo {
+        self.patterns.retain(|pattern| matches!(pattern, WinPattern::Custom { .. }));
+        self.patterns.splice(0..0, patterns);
+        self
+    }
+
+    /// Draws every number and ranks the boards by when they win, leaving the game
+    /// as it is. Only boards holding a drawn number are touched, and per-line
+    /// counters tell when a board wins.
+    pub fn simulate(&self) -> Ranking {
+        self.simulate_with(&self.numbers)
+    }
+
+    /// Like `simulate`, drawing `numbers` instead of the game's numbers.
+    pub fn simulate_with(&self, numbers: &[u32]) -> Ranking {
+        let mut ranking = Ranking { finishes: vec![], never: vec![] };
+        let Some(first) = self.boards.first() else {
+            return ranking;
+        };
+        let (rows, columns) = (first.rows(), first.columns());
+        let layout = LineLayout::new(&self.patterns, rows, columns);
+        let mut counters: Vec<LineCounter> = self.boards.iter().map(|_| LineCounter::new(&layout)).collect();
+        let mut drawn = vec![false; self.boards.len() * rows * columns];
+        let mut unmarked: Vec<u64> = self.boards.iter().map(Board::unmarked_sum).collect();
+        let mut won = vec![false; self.boards.len()];
+
+        for (draw, &number) in numbers.iter().enumerate() {
+            let occurrences = self.index.get(&number).map_or(&[][..], Vec::as_slice);
+            for cells in occurrences.chunk_by(|a, b| a.0 == b.0) {
+                let n = cells[0].0;
+                let mut completed: Option<usize> = None;
+                for &(_, (i, j)) in cells {
+                    let cell = &mut drawn[(n * rows + i) * columns + j];
+                    if std::mem::replace(cell, true) {
+                        continue;
+                    }
+                    unmarked[n] -= u64::from(number);
+                    if let Some(pattern) = counters[n].hit(&layout, (i, j)) {
+                        completed = Some(completed.map_or(pattern, |c| c.min(pattern)));
+                    }
+                }
+                if let Some(pattern) = completed.filter(|_| !won[n]) {
+                    won[n] = true;
+                    let score = unmarked[n] * u64::from(number);
+                    ranking.finishes.push(Finish { board: n, draw, number, pattern: self.patterns[pattern].clone(), score });
+                }
+            }
+            if ranking.finishes.len() == self.boards.len() {
+                break;
             }
         }
-        result * self.winning_number
+        ranking.never = (0..self.boards.len()).filter(|&n| !won[n]).collect();
+        ranking
+    }
+
+    /// Simulates the game and picks one finish from the ranking.
+    pub fn play(&self, strat: Strategy) -> Result<Finish, &'static str> {
+        self.simulate().query(strat).cloned().ok_or("could not find a winning board")
+    }
+
+    pub fn boards(&self) -> &[Board] {
+        &self.boards
+    }
+
+    pub fn numbers(&self) -> &[u32] {
+        &self.numbers
     }
 }
 
-#[derive(Copy)]
+/// A bingo card of any size, stored row by row.
 #[derive(Clone)]
-struct Board {
-    fields: [[Field; 5]; 5],
+pub struct Board {
+    fields: Vec<Vec<Field>>,
+    /// The cells holding each number, so marking does not scan the board.
+    index: HashMap<u32, Vec<Cell>>,
 }
 
 impl Board {
-    fn new() -> Board {
-        Board { fields: [[Field::new(); 5]; 5] }
-    }
-    fn builder(s: &str) -> Board {
-        let mut board = Board::new();
-        for (i, line) in s.lines().enumerate() {
-            for (j, number) in line.split_whitespace().filter_map(|x| x.parse().ok()).enumerate() {
-                board.fields[i][j].value = number;
-                board.fields[i][j].drawn = false;
+    pub fn new(rows: usize, columns: usize) -> Board {
+        Board::from_fields(vec![vec![Field::new(); columns]; rows])
+    }
+
+    fn from_fields(fields: Vec<Vec<Field>>) -> Board {
+        let mut index: HashMap<u32, Vec<Cell>> = HashMap::new();
+        for (i, row) in fields.iter().enumerate() {
+            for (j, field) in row.iter().enumerate() {
+                index.entry(field.value).or_default().push((i, j));
             }
         }
-        board
+        Board { fields, index }
     }
-    fn mark(&mut self, number: &u32) -> &Board {
-        for field in self.fields.iter_mut().flat_map(|r| r.iter_mut()) {
-            if field.value == *number {
-                field.drawn = true;
-                break;
+
+    /// One row per line, numbers separated by whitespace. Every row needs the same
+    /// number of cells.
+    pub fn builder(s: &str) -> Result<Board, BingoError> {
+        let lines: Vec<(usize, &str)> =
+            s.lines().enumerate().map(|(i, line)| (i + 1, line)).filter(|(_, line)| !line.trim().is_empty()).collect();
+        Board::from_lines(&lines)
+    }
+
+    /// Rows with their line numbers in the input.
+    fn from_lines(lines: &[(usize, &str)]) -> Result<Board, BingoError> {
+        let mut fields: Vec<Vec<Field>> = vec![];
+        for &(line_no, line) in lines {
+            let row: Vec<Field> =
+                row_numbers(line_no, line).map(|value| value.map(|value| Field { value, drawn: false })).collect::<Result<_, _>>()?;
+            if let Some(first) = fields.first() {
+                if row.len() != first.len() {
+                    return Err(BingoError::Ragged { line: line_no, columns: row.len(), expected: first.len() });
+                }
             }
+            fields.push(row);
         }
-        self
+        Ok(Board::from_fields(fields))
     }
-    fn has_bingo(&self) -> bool {
-        for i in 0..5{
-            let mut count1 = 0;
-            let mut count2 = 0;
-            for j in 0..5{
-                if self.fields[i][j].drawn {
-                    count1 += 1;
-                    if count1 >= 5{
-                        return true;
-                    }
-                };
-                if self.fields[j][i].drawn {
-                    count2 += 1;
-                    if count2 >= 5{
-                        return true;
-                    }
-                };
-            }
+
+    pub fn rows(&self) -> usize {
+        self.fields.len()
+    }
+
+    pub fn columns(&self) -> usize {
+        self.fields.first().map_or(0, |row| row.len())
+    }
+
+    pub fn value(&self, row: usize, column: usize) -> u32 {
+        self.fields[row][column].value
+    }
+
+    pub fn is_drawn(&self, row: usize, column: usize) -> bool {
+        self.fields[row][column].drawn
+    }
+
+    /// Marks every cell holding `number` and returns the ones that were not marked yet.
+    pub fn mark(&mut self, number: &u32) -> Vec<Cell> {
+        let cells = self.index.get(number).cloned().unwrap_or_default();
+        cells.into_iter().filter(|&cell| self.draw(cell)).collect()
+    }
+
+    /// Marks a cell, telling whether it was not marked yet.
+    fn draw(&mut self, (i, j): Cell) -> bool {
+        !std::mem::replace(&mut self.fields[i][j].drawn, true)
+    }
+
+    /// The sum of the unmarked numbers.
+    pub fn unmarked_sum(&self) -> u64 {
+        self.fields.iter().flatten().filter(|field| !field.drawn).map(|field| u64::from(field.value)).sum()
+    }
+
+    /// The unmarked sum times the number that was drawn last.
+    pub fn score(&self, number: u32) -> u64 {
+        self.unmarked_sum() * u64::from(number)
+    }
+
+    /// Clears every mark.
+    pub fn reset(&mut self) {
+        for field in self.fields.iter_mut().flatten() {
+            field.drawn = false;
         }
-        false
+    }
+
+    /// A full row or a full column.
+    pub fn has_bingo(&self) -> bool {
+        self.completed(&[WinPattern::Rows, WinPattern::Columns]).is_some()
+    }
+
+    /// The first of `patterns` that is fully drawn on this board.
+    pub fn completed<'a>(&self, patterns: &'a [WinPattern]) -> Option<&'a WinPattern> {
+        patterns.iter().find(|pattern| {
+            pattern
+                .lines(self.rows(), self.columns())
+                .iter()
+                .any(|line| line.iter().all(|&(i, j)| self.fields[i][j].drawn))
+        })
     }
 }
 
+/// One line per row, numbers aligned, drawn numbers in brackets.
 impl std::fmt::Display for Board {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        let mut output = "".to_string();
-        for (i, row) in self.fields.iter().enumerate() {
-            for (j, field) in row.iter().enumerate() {
-                output.push_str(format!("{}/{}/={},{}  \t", i, j, field.value, field.drawn).as_str());
-            }
-            output.push('\n');
+        let width = self.fields.iter().flatten().map(|field| field.value.to_string().len()).max().unwrap_or(0);
+        for row in &self.fields {
+            let cells: Vec<String> = row
+                .iter()
+                .map(|field| match field.drawn {
+                    true => format!("[{:>width$}]", field.value),
+                    false => format!(" {:>width$} ", field.value),
+                })
+                .collect();
+            writeln!(f, "{}", cells.join(" ").trim_end())?;
         }
-        write!(f, "{}", output)
+        Ok(())
     }
 }
 
 #[derive(Copy)]
 #[derive(Clone)]
-struct Field {
+pub struct Field {
     value: u32,
     drawn: bool,
 }
@@ -190,78 +690,278 @@ impl Field {
 
 pub fn run(config: Config) -> Result<(), Box<dyn Error>>
 {
-    let content = fs::read_to_string(config.file_path)?.replace("\r\n", "\n");
-    let mut sub = Submarine::builder(content.as_str());
-    println!("Win {}", sub.bingo.play(Strategy::Win)?.get());
-    println!("Lose {}", sub.bingo.play(Strategy::Lose)?.get());
+    let file_path = match &config.task {
+        Task::Client { addr, auto } => return client(addr, *auto),
+        Task::Generate { file_path, spec } => {
+            fs::write(file_path, generate::generate(spec)?)?;
+            println!("Wrote {} boards of {}x{} to {}", spec.boards, spec.rows, spec.columns, file_path);
+            return Ok(());
+        }
+        Task::Solve { file_path }
+        | Task::Replay { file_path, .. }
+        | Task::Odds { file_path, .. }
+        | Task::Play { file_path }
+        | Task::Force { file_path, .. }
+        | Task::Serve { file_path, .. }
+        | Task::Lint { file_path } => file_path,
+    };
+    let content = fs::read_to_string(file_path)?.replace("\r\n", "\n");
+    let issues = lint::lint(&content, config.patterns.as_deref());
+    for issue in &issues {
+        eprintln!("{}", issue);
+    }
+    let errors = issues.iter().filter(|issue| issue.is_error()).count();
+    if errors > 0 {
+        return Err(format!("{} cannot be played, see the errors above", file_path).into());
+    }
+    if let Task::Lint { .. } = config.task {
+        println!("{} warnings", issues.len());
+        return Ok(());
+    }
+    let mut sub = Submarine::builder(content.as_str())?;
+    if let Some(patterns) = config.patterns {
+        sub.bingo = sub.bingo.with_patterns(patterns);
+    }
+    if let Task::Replay { log_path, .. } = &config.task {
+        let log = EventLog::parse(&fs::read_to_string(log_path)?, &sub.bingo.patterns)?;
+        return replay(Replay::new(&sub.bingo, log)?);
+    }
+    if let Task::Serve { addr, interval, .. } = config.task {
+        return serve(sub.bingo, &addr, interval);
+    }
+    if let Task::Play { .. } = config.task {
+        return play(&sub.bingo);
+    }
+    if let Task::Force { board, goal, limit, .. } = config.task {
+        return force(&sub.bingo, board, goal, limit);
+    }
+    if let Task::Odds { trials, seed, threads, .. } = config.task {
+        let threads = threads.unwrap_or_else(|| thread::available_parallelism().map_or(1, |n| n.get()));
+        let odds = odds::estimate(&sub.bingo, trials, seed, threads);
+        println!("{} trials, a fair share is {:.2}%", trials, 100.0 / odds.len() as f64);
+        let percent = |e: odds::Estimate| format!("{:.2}% [{:.2}%, {:.2}%]", 100.0 * e.value, 100.0 * e.low, 100.0 * e.high);
+        for (n, board) in odds.iter().enumerate() {
+            let draws = match board.draws {
+                Some(e) => format!("{:.1} draws [{:.1}, {:.1}]", e.value, e.low, e.high),
+                None => format!("won {} times", board.wins),
+            };
+            println!("Board {}: first {}, last {}, {}", n + 1, percent(board.first), percent(board.last), draws);
+        }
+        return Ok(());
+    }
+    if let Some(path) = &config.log {
+        fs::write(path, Game::new(&sub.bingo).play_out().to_string())?;
+    }
+    let ranking = sub.bingo.simulate();
+    let win = ranking.query(Strategy::Win).ok_or("could not find a winning board")?;
+    println!("Win {} ({})", win.score, win.pattern);
+    match ranking.query(Strategy::Lose) {
+        Some(lose) => println!("Lose {} ({})", lose.score, lose.pattern),
+        None => println!("Boards {:?} never win", ranking.never.iter().map(|n| n + 1).collect::<Vec<_>>()),
+    }
+    if let Some(place) = config.nth {
+        match ranking.query(Strategy::Nth(place)) {
+            Some(finish) => println!("Winner {} is board {} with {} ({})", place, finish.board + 1, finish.score, finish.pattern),
+            None => println!("Only {} boards win", ranking.finishes.len()),
+        }
+    }
+    Ok(())
+}
+
+fn force(bingo: &Bingo, board: usize, goal: solver::Goal, limit: Duration) -> Result<(), Box<dyn Error>> {
+    if board >= bingo.boards.len() {
+        return Err(format!("there are only {} boards", bingo.boards.len()).into());
+    }
+    match solver::solve(bingo, board, goal, limit) {
+        solver::Search::Found { draws, optimal } => {
+            let numbers: Vec<String> = draws.iter().map(u32::to_string).collect();
+            println!("{} draws: {}", draws.len(), numbers.join(","));
+            if !optimal {
+                println!("The time ran out, a shorter order may exist");
+            }
+        }
+        solver::Search::Impossible { lines } => {
+            println!("Impossible");
+            for (cells, blocked) in lines {
+                println!("{}: {}", describe_cells(&cells), blocked);
+            }
+        }
+        solver::Search::TimedOut => println!("The time ran out before an order was found"),
+    }
     Ok(())
 }
 
+/// Cells as rows and columns counted from 1, like `row 1 col 2, row 2 col 2`.
+fn describe_cells(cells: &[Cell]) -> String {
+    let cells: Vec<String> = cells.iter().map(|&(i, j)| format!("row {} col {}", i + 1, j + 1)).collect();
+    cells.join(", ")
+}
+
+/// Draws every `interval`, or on each line read from stdin without one, and prints
+/// the draws and the winners as their claims come in.
+fn serve(bingo: Bingo, addr: &str, interval: Option<Duration>) -> Result<(), Box<dyn Error>> {
+    let boards = bingo.boards.len();
+    let server = net::Server::bind(addr, bingo)?;
+    println!("Serving {} boards on {}", boards, server.local_addr()?);
+    let handle = server.serve();
+    if let Some(interval) = interval {
+        handle.draw_every(interval);
+    }
+    let mut announced = 0;
+    let mut lines = io::stdin().lock().lines();
+    loop {
+        match interval {
+            Some(interval) => thread::sleep(interval),
+            None => match lines.next().transpose()? {
+                Some(line) if line.trim() == "quit" => break,
+                Some(_) => {
+                    handle.draw();
+                }
+                None => break,
+            },
+        }
+        let history = handle.history();
+        for message in &history[announced..] {
+            match message {
+                net::Message::Draw { number } => println!("Draw {} ({} players)", number, handle.players()),
+                net::Message::Winner { board, score, pattern } => {
+                    println!("Board {} wins with {}, score {}", board + 1, pattern, score)
+                }
+                net::Message::Over => return Ok(()),
+                _ => {}
+            }
+        }
+        announced = history.len();
+    }
+    Ok(())
+}
+
+/// Claims on each `bingo` line from stdin, or as soon as the board has bingo with
+/// `auto`.
+fn client(addr: &str, auto: bool) -> Result<(), Box<dyn Error>> {
+    let mut client = net::Client::connect(addr)?;
+    println!("Playing board {}\n{}", client.index() + 1, client.board());
+    if !auto {
+        let claimer = client.claimer()?;
+        thread::spawn(move || {
+            for line in io::stdin().lock().lines().map_while(Result::ok) {
+                if line.trim() == "bingo" && claimer.claim().is_err() {
+                    break;
+                }
+            }
+        });
+    }
+    let mut claimed = false;
+    while let Some(message) = client.recv()? {
+        match message {
+            net::Message::Draw { number } => {
+                println!("Draw {}\n{}", number, client.board());
+                if auto && !claimed && client.board().has_bingo() {
+                    client.claim()?;
+                    claimed = true;
+                }
+            }
+            net::Message::Winner { board, score, pattern } => {
+                println!("Board {} wins with {}, score {}", board + 1, pattern, score)
+            }
+            net::Message::Rejected { reason } => println!("Claim rejected: {}", reason),
+            net::Message::Over => break,
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Highlights with colours unless `NO_COLOR` is set, and lays the boards out in
+/// `COLUMNS`, or 80, columns.
+fn play(bingo: &Bingo) -> Result<(), Box<dyn Error>> {
+    let colour = env::var_os("NO_COLOR").is_none();
+    let width = env::var("COLUMNS").ok().and_then(|columns| columns.parse().ok()).unwrap_or(80);
+    let mut session = terminal::Session::new(bingo, colour, width);
+    print!("{}> ", session.render());
+    io::stdout().flush()?;
+    for line in io::stdin().lock().lines() {
+        match session.execute(&line?) {
+            Some(Ok(output)) => print!("{}", output),
+            Some(Err(e)) => println!("{}", e),
+            None => break,
+        }
+        print!("> ");
+        io::stdout().flush()?;
+    }
+    Ok(())
+}
+
+/// Reads commands from stdin: enter or `n` for the next event, `b` to go back,
+/// `g <event>` to jump and `q` to quit.
+fn replay(mut replay: Replay) -> Result<(), Box<dyn Error>> {
+    println!("{}", replay.render());
+    for line in io::stdin().lock().lines() {
+        let line = line?;
+        match line.split_whitespace().collect::<Vec<_>>()[..] {
+            [] | ["n"] => {
+                if replay.forward().is_none() {
+                    println!("End of the log");
+                    continue;
+                }
+            }
+            ["b"] => {
+                if !replay.back() {
+                    println!("Start of the log");
+                    continue;
+                }
+            }
+            ["g", position] => match position.parse().map(|position| replay.goto(position)) {
+                Ok(Ok(())) => {}
+                Ok(Err(e)) => {
+                    println!("{}", e);
+                    continue;
+                }
+                Err(_) => {
+                    println!("Expected an event number after g");
+                    continue;
+                }
+            },
+            ["q"] => break,
+            _ => {
+                println!("Expected n, b, g <event> or q");
+                continue;
+            }
+        }
+        println!("{}", replay.render());
+    }
+    Ok(())
+}
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::fixtures::GAME;
 
     #[test]
     fn test_bingo_play_to_win() {
-        let content: &str = "7,4,9,5,11,17,23,2,0,14,21,24,10,16,13,6,15,25,12,22,18,20,8,19,3,26,1
-
-22 13 17 11  0
- 8  2 23  4 24
-21  9 14 16  7
- 6 10  3 18  5
- 1 12 20 15 19
-
- 3 15  0  2 22
- 9 18 13 17  5
-19  8  7 25 23
-20 11 10 24  4
-14 21 16 12  6
-
-14 21 17 24  4
-10 16 15  9 19
-18  8 23 26 20
-22 11 13  6  5
- 2  0 12  3  7";
-        let mut bingo = Bingo::builder(content);
-        bingo.play(Strategy::Win).expect("help");
+        let content = GAME;
+        let bingo = Bingo::builder(content).unwrap();
+        let finish = bingo.play(Strategy::Win).expect("help");
         for board in &bingo.boards {
             println!("{}", board);
         }
-        assert_eq!(24, bingo.winning_number);
-        assert_eq!(24, bingo.winning_board.fields[0][3].value);
-        assert_eq!(4512, bingo.get());
+        assert_eq!(24, finish.number);
+        assert_eq!(24, bingo.boards[finish.board].fields[0][3].value);
+        assert_eq!(4512, finish.score);
     }
 
     #[test]
     fn test_bingo_play_to_lose() {
-        let content: &str = "7,4,9,5,11,17,23,2,0,14,21,24,10,16,13,6,15,25,12,22,18,20,8,19,3,26,1
-
-22 13 17 11  0
- 8  2 23  4 24
-21  9 14 16  7
- 6 10  3 18  5
- 1 12 20 15 19
-
- 3 15  0  2 22
- 9 18 13 17  5
-19  8  7 25 23
-20 11 10 24  4
-14 21 16 12  6
-
-14 21 17 24  4
-10 16 15  9 19
-18  8 23 26 20
-22 11 13  6  5
- 2  0 12  3  7";
-        let mut bingo = Bingo::builder(content);
-        bingo.play(Strategy::Lose).expect("help");
+        let content = GAME;
+        let bingo = Bingo::builder(content).unwrap();
+        let finish = bingo.play(Strategy::Lose).expect("help");
         for board in &bingo.boards {
             println!("{}", board);
         }
-        assert_eq!(13, bingo.winning_number);
-        assert_eq!(2, bingo.winning_board.fields[0][3].value);
-        assert_eq!(1924, bingo.get());
+        assert_eq!(13, finish.number);
+        assert_eq!(2, bingo.boards[finish.board].fields[0][3].value);
+        assert_eq!(1924, finish.score);
     }
 
     #[test]
@@ -270,10 +970,10 @@ mod tests {
                                           8  2 23  4 24
                                           21  9 14 16  7
                                           6 10  3 18  5
-                                          1 12 20 15 19");
-        assert_eq!(false, board.fields[0][0].drawn);
+                                          1 12 20 15 19").unwrap();
+        assert!(!board.fields[0][0].drawn);
         board.mark(&22);
-        assert_eq!(true, board.fields[0][0].drawn);
+        assert!(board.fields[0][0].drawn);
     }
 
     #[test]
@@ -282,12 +982,12 @@ mod tests {
                                           8  2 23  4 24
                                           21  9 14 16  7
                                           6 10  3 18  5
-                                          1 12 20 15 19");
-        assert_eq!(false, board.has_bingo());
+                                          1 12 20 15 19").unwrap();
+        assert!(!board.has_bingo());
         for i in 0..5 {
             board.fields[1][i].drawn = true;
         }
-        assert_eq!(true, board.has_bingo());
+        assert!(board.has_bingo());
     }
 
     #[test]
@@ -296,57 +996,32 @@ mod tests {
                                           8  2 23  4 24
                                           21  9 14 16  7
                                           6 10  3 18  5
-                                          1 12 20 15 19");
-        assert_eq!(false, board.has_bingo());
+                                          1 12 20 15 19").unwrap();
+        assert!(!board.has_bingo());
         for i in 0..5 {
             board.fields[i][1].drawn = true;
         }
-        assert_eq!(true, board.has_bingo());
+        assert!(board.has_bingo());
     }
 
     #[test]
     fn test_bingo_get()
     {
-        let bingo: Bingo = Bingo {
-            numbers: vec![],
-            boards: vec![],
-            winning_number: 24,
-            winning_board: Board {
-                fields: [
-                    [Field { value: 14, drawn: true }, Field { value: 21, drawn: true }, Field { value: 17, drawn: true }, Field { value: 24, drawn: true }, Field { value: 4, drawn: true }],
-                    [Field { value: 10, drawn: false }, Field { value: 16, drawn: false }, Field { value: 15, drawn: false }, Field { value: 9, drawn: true }, Field { value: 19, drawn: false }],
-                    [Field { value: 18, drawn: false }, Field { value: 8, drawn: false }, Field { value: 23, drawn: true }, Field { value: 26, drawn: false }, Field { value: 20, drawn: false }],
-                    [Field { value: 22, drawn: false }, Field { value: 11, drawn: true }, Field { value: 13, drawn: false }, Field { value: 6, drawn: false }, Field { value: 5, drawn: true }],
-                    [Field { value: 2, drawn: true }, Field { value: 0, drawn: true }, Field { value: 12, drawn: false }, Field { value: 3, drawn: false }, Field { value: 7, drawn: true }], ]
-            },
-        };
+        let board = Board::from_fields(vec![
+                    vec![Field { value: 14, drawn: true }, Field { value: 21, drawn: true }, Field { value: 17, drawn: true }, Field { value: 24, drawn: true }, Field { value: 4, drawn: true }],
+                    vec![Field { value: 10, drawn: false }, Field { value: 16, drawn: false }, Field { value: 15, drawn: false }, Field { value: 9, drawn: true }, Field { value: 19, drawn: false }],
+                    vec![Field { value: 18, drawn: false }, Field { value: 8, drawn: false }, Field { value: 23, drawn: true }, Field { value: 26, drawn: false }, Field { value: 20, drawn: false }],
+                    vec![Field { value: 22, drawn: false }, Field { value: 11, drawn: true }, Field { value: 13, drawn: false }, Field { value: 6, drawn: false }, Field { value: 5, drawn: true }],
+                    vec![Field { value: 2, drawn: true }, Field { value: 0, drawn: true }, Field { value: 12, drawn: false }, Field { value: 3, drawn: false }, Field { value: 7, drawn: true }], ]);
 
-        assert_eq!(4512, bingo.get());
+        assert_eq!(4512, board.score(24));
     }
 
     #[test]
     fn test_bingo_from()
     {
-        let content: &str = "7,4,9,5,11,17,23,2,0,14,21,24,10,16,13,6,15,25,12,22,18,20,8,19,3,26,1
-
-22 13 17 11  0
- 8  2 23  4 24
-21  9 14 16  7
- 6 10  3 18  5
- 1 12 20 15 19
-
- 3 15  0  2 22
- 9 18 13 17  5
-19  8  7 25 23
-20 11 10 24  4
-14 21 16 12  6
-
-14 21 17 24  4
-10 16 15  9 19
-18  8 23 26 20
-22 11 13  6  5
- 2  0 12  3  7";
-        let bingo = Bingo::builder(content);
+        let content = GAME;
+        let bingo = Bingo::builder(content).unwrap();
         assert_eq!(18, bingo.boards.get(1).expect("").fields[1][1].value);
     }
 
@@ -357,10 +1032,171 @@ mod tests {
 18  8 23 26 20
 22 11 13  6  5
  2  1 12  3  7";
-        let board: Board = Board::builder(content);
+        let board: Board = Board::builder(content).unwrap();
         println!("{}", board);
         assert_eq!(16, board.fields[1][1].value);
         assert_eq!(21, board.fields[0][1].value);
         assert_eq!(3, board.fields[4][3].value);
     }
+
+    #[test]
+    fn rectangular_boards() {
+        let content = "5,1,9,2,7,3
+
+1 2 3
+4 5 6
+
+7 8 9
+1 5 3";
+        let bingo = Bingo::builder(content).unwrap();
+        assert_eq!((2, 3), (bingo.boards[0].rows(), bingo.boards[0].columns()));
+        let finish = bingo.play(Strategy::Win).unwrap();
+        // 5 and 2 complete the middle column of the first board
+        assert_eq!(2, finish.number);
+        assert_eq!((3 + 4 + 6) * 2, finish.score);
+
+        let mut board = Board::builder("1 2 3 4 5 6\n7 8 9 10 11 12").unwrap();
+        for number in [4, 10] {
+            assert!(!board.has_bingo());
+            board.mark(&number);
+        }
+        assert!(board.has_bingo());
+    }
+
+    fn config(args: &[&str]) -> Result<Config, String> {
+        Config::build(["day4"].iter().chain(args).map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn config_per_task() {
+        let solve = config(&["data.txt", "--nth", "3", "--patterns", "rows,x"]).unwrap();
+        assert_eq!(Task::Solve { file_path: "data.txt".to_string() }, solve.task);
+        assert_eq!((Some(3), Some(vec![WinPattern::Rows, WinPattern::X])), (solve.nth, solve.patterns));
+        let task = config(&["force", "data.txt", "2", "--last", "--seconds", "0.5"]).unwrap().task;
+        assert_eq!(Task::Force { file_path: "data.txt".to_string(), board: 1, goal: solver::Goal::LoseLast, limit: Duration::from_millis(500) }, task);
+        let Task::Generate { spec, .. } = config(&["generate", "out.txt", "--size", "3x4", "--unique", "--wins-last", "2", "--seed", "5"]).unwrap().task else {
+            panic!("expected generate");
+        };
+        assert_eq!((3, 4, true, Some((1, Strategy::Lose)), 5), (spec.rows, spec.columns, spec.unique, spec.winner, spec.seed));
+
+        assert_eq!(Err("unknown option '--seed' for play".to_string()), config(&["play", "data.txt", "--seed", "1"]).map(|c| c.task));
+        assert_eq!(Err("unknown option '--patterns' for generate".to_string()), config(&["generate", "out.txt", "--patterns", "x"]).map(|c| c.task));
+        assert_eq!(Err("unknown option '--nth' for odds".to_string()), config(&["odds", "data.txt", "--nth", "2"]).map(|c| c.task));
+        assert_eq!(Err("Expected a positive number after --trials".to_string()), config(&["odds", "data.txt", "--trials", "0"]).map(|c| c.task));
+        // files named like a task
+        assert_eq!(Task::Solve { file_path: "odds".to_string() }, config(&["--", "odds"]).unwrap().task);
+        assert_eq!(Task::Solve { file_path: "./lint".to_string() }, config(&["./lint"]).unwrap().task);
+    }
+
+    #[test]
+    fn cells_are_described_from_1() {
+        assert_eq!("row 1 col 2, row 3 col 4", describe_cells(&[(0, 1), (2, 3)]));
+    }
+
+    #[test]
+    fn tall_boards() {
+        let bingo = Bingo::builder("1,3,5,2,4,6\n\n1 2\n3 4\n5 6").unwrap();
+        let finish = bingo.play(Strategy::Win).unwrap();
+        // 1, 3 and 5 complete the first column
+        assert_eq!((2, 5, WinPattern::Columns), (finish.draw, finish.number, finish.pattern));
+        assert_eq!((2 + 4 + 6) * 5, finish.score);
+        let bingo = bingo.with_patterns(vec![WinPattern::Diagonals, WinPattern::Corners]);
+        assert_eq!(5, bingo.play(Strategy::Win).unwrap().draw);
+    }
+
+    #[test]
+    fn invalid_boards() {
+        assert_eq!(Err(BingoError::Ragged { line: 2, columns: 2, expected: 3 }).map(|_: Board| ()),
+                   Board::builder("1 2 3\n4 5").map(|_| ()));
+        assert_eq!(Err(BingoError::Ragged { line: 4, columns: 4, expected: 3 }).map(|_: Bingo| ()),
+                   Bingo::builder("1,2\n\n1 2 3\n4 5 6 7").map(|_| ()));
+        assert_eq!(Err(BingoError::Mismatched { line: 6, rows: 1, columns: 2, expected_rows: 2, expected_columns: 2 }).map(|_: Bingo| ()),
+                   Bingo::builder("1,2\n\n1 2\n3 4\n\n5 6\n").map(|_| ()));
+        assert_eq!(Err(BingoError::BadNumber { line: 1, found: "x".to_string() }).map(|_: Bingo| ()),
+                   Bingo::builder("1,x\n\n1").map(|_| ()));
+        assert_eq!(Err(BingoError::NoBoards).map(|_: Bingo| ()), Bingo::builder("1,2\n\n\n").map(|_| ()));
+    }
+
+    #[test]
+    fn winning_pattern_is_reported() {
+        let content = GAME;
+        let bingo = Bingo::builder(content).unwrap();
+        assert_eq!(WinPattern::Rows, bingo.play(Strategy::Win).unwrap().pattern);
+
+        // the third board's corners 14, 4, 2 and 7 are all drawn by 14, its first row needs 24
+        let bingo = Bingo::builder(content).unwrap().with_patterns(vec![WinPattern::Corners]);
+        let finish = bingo.play(Strategy::Win).unwrap();
+        assert_eq!(WinPattern::Corners, finish.pattern);
+        assert_eq!(14, finish.number);
+        assert_eq!(14, bingo.boards[finish.board].fields[0][0].value);
+    }
+
+    #[test]
+    fn custom_patterns_from_the_input() {
+        let content = "1,5,9,3,7
+
+pattern plus
+0 1 0
+1 1 1
+0 1 0
+
+1 2 3
+4 5 6
+7 8 9
+
+pattern ends
+101
+000
+101";
+        let bingo = Bingo::builder(content).unwrap().with_patterns(vec![WinPattern::Diagonals]);
+        assert_eq!(3, bingo.patterns.len());
+        let finish = bingo.play(Strategy::Win).unwrap();
+        assert_eq!(WinPattern::Diagonals, finish.pattern);
+        assert_eq!(9, finish.number);
+
+        let bingo = Bingo::builder(content).unwrap().with_patterns(vec![]);
+        assert_eq!("ends", bingo.play(Strategy::Win).unwrap().pattern.to_string());
+
+        assert_eq!(Err(BingoError::PatternSize { line: 7, rows: 1, columns: 2, expected_rows: 3, expected_columns: 3 }).map(|_: Bingo| ()),
+                   Bingo::builder("1,2\n\n1 2 3\n4 5 6\n7 8 9\n\npattern small\n11").map(|_| ()));
+    }
+
+    #[test]
+    fn duplicate_numbers() {
+        let mut board = Board::builder("1 2 1\n3 1 4").unwrap();
+        assert_eq!(vec![(0, 0), (0, 2), (1, 1)], board.mark(&1));
+        assert!(board.mark(&1).is_empty());
+        assert!(!board.has_bingo());
+        assert_eq!(vec![(0, 1)], board.mark(&2));
+        assert!(board.has_bingo());
+
+        // a repeated draw does not count twice towards a line
+        let bingo = Bingo::builder("5,5,6,7\n\n5 6\n7 8\n\n5 8\n7 6").unwrap();
+        assert_eq!(6, bingo.play(Strategy::Win).unwrap().number);
+        let finish = bingo.play(Strategy::Lose).unwrap();
+        assert_eq!(7, finish.number);
+        assert_eq!(8 * 7, finish.score);
+    }
+
+    #[test]
+    fn full_ranking() {
+        let content = &format!("{}\n\n90 91 92 93 94\n95 96 97 98 99\n50 51 52 53 54\n55 56 57 58 59\n60 61 62 63 64", GAME);
+        let bingo = Bingo::builder(content).unwrap().with_patterns(vec![WinPattern::Rows, WinPattern::Columns]);
+        let ranking = bingo.simulate();
+        let order: Vec<(usize, usize, u32)> = ranking.finishes.iter().map(|f| (f.board, f.draw, f.number)).collect();
+        assert_eq!(vec![(2, 11, 24), (0, 13, 16), (1, 14, 13)], order);
+        assert_eq!(vec![3], ranking.never);
+        // the game itself is left unmarked
+        assert!(bingo.boards.iter().all(|board| (0..board.rows()).all(|i| (0..board.columns()).all(|j| !board.is_drawn(i, j)))));
+
+        assert_eq!(Some(4512), ranking.query(Strategy::Win).map(|f| f.score));
+        assert_eq!(None, ranking.query(Strategy::Lose));
+        assert_eq!(Some(0), ranking.query(Strategy::Nth(2)).map(|f| f.board));
+        assert_eq!(Some(1924), ranking.query(Strategy::Nth(3)).map(|f| f.score));
+        assert_eq!(None, ranking.query(Strategy::Nth(4)));
+        assert_eq!(None, ranking.query(Strategy::Nth(0)));
+        assert_eq!(Some(3), ranking.of(1).map(|(place, _)| place));
+        assert_eq!(None, ranking.of(3));
+        assert_eq!(ranking, bingo.simulate());
+    }
 }
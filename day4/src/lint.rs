@@ -0,0 +1,249 @@
+//! Problems in an input file, all of them rather than the first one
+//! `Bingo::builder` stops at. Errors keep the game from being played at all;
+//! warnings point at numbers and boards that do not take part in it. The file is
+//! split and its numbers read the way `Bingo::builder` does it.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt::Formatter;
+
+use crate::pattern::{Cell, WinPattern};
+use crate::{drawn_numbers, is_pattern, row_numbers, Bingo, BingoError, Sections};
+
+#[derive(Debug, PartialEq)]
+pub enum Problem {
+    NoNumbers,
+    NoBoards,
+    BadNumber { found: String },
+    /// A row with a different number of cells than the first row of its board.
+    Ragged { columns: usize, expected: usize },
+    /// A board with different dimensions than the first board.
+    Dimensions { rows: usize, columns: usize, expected_rows: usize, expected_columns: usize },
+    DuplicateNumber { number: u32 },
+    /// A number on the boards that is never drawn, with the board and cell of each
+    /// place it is on.
+    NeverDrawn { number: u32, cells: Vec<(usize, Cell)> },
+    DuplicateDraw { number: u32 },
+    NeverWins,
+    /// Anything else `Bingo::builder` rejects, such as a bad pattern.
+    Invalid(BingoError),
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Issue {
+    /// Index of the board, for problems with a board.
+    pub board: Option<usize>,
+    pub line: usize,
+    pub problem: Problem,
+}
+
+impl Issue {
+    pub fn is_error(&self) -> bool {
+        !matches!(
+            self.problem,
+            Problem::DuplicateNumber { .. } | Problem::NeverDrawn { .. } | Problem::DuplicateDraw { .. } | Problem::NeverWins
+        )
+    }
+}
+
+impl std::fmt::Display for Problem {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Problem::NoNumbers => write!(f, "no drawn numbers"),
+            Problem::NoBoards => write!(f, "no boards"),
+            Problem::BadNumber { found } => write!(f, "'{}' is not a number", found),
+            Problem::Ragged { columns, expected } => write!(f, "{} numbers, the rows above have {}", columns, expected),
+            Problem::Dimensions { rows, columns, expected_rows, expected_columns } => {
+                write!(f, "the board is {}x{}, the first board is {}x{}", rows, columns, expected_rows, expected_columns)
+            }
+            Problem::DuplicateNumber { number } => write!(f, "{} is on the board more than once", number),
+            Problem::NeverDrawn { number, cells } => {
+                let cells: Vec<String> =
+                    cells.iter().map(|(board, (i, j))| format!("board {} row {} column {}", board + 1, i + 1, j + 1)).collect();
+                write!(f, "{} is never drawn, on {}", number, cells.join(", "))
+            }
+            Problem::DuplicateDraw { number } => write!(f, "{} is drawn more than once", number),
+            Problem::NeverWins => write!(f, "the board can never win"),
+            Problem::Invalid(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+/// Boards are counted from 1, as in the rest of the output.
+impl std::fmt::Display for Issue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let level = if self.is_error() { "error" } else { "warning" };
+        match self.board {
+            Some(board) => write!(f, "{}: board {}, line {}: {}", level, board + 1, self.line, self.problem),
+            None => write!(f, "{}: line {}: {}", level, self.line, self.problem),
+        }
+    }
+}
+
+/// Checks an input file, played with `patterns` instead of rows and columns if
+/// given. Boards that can never win are only looked for when there are no errors.
+pub fn lint(s: &str, patterns: Option<&[WinPattern]>) -> Vec<Issue> {
+    let mut issues = vec![];
+    let sections = Sections::new(s);
+
+    let mut drawn = HashSet::new();
+    match sections.numbers {
+        Some((line, numbers)) => {
+            for number in drawn_numbers(line, numbers) {
+                match number {
+                    Ok(number) if !drawn.insert(number) => {
+                        issues.push(Issue { board: None, line, problem: Problem::DuplicateDraw { number } })
+                    }
+                    Ok(_) => {}
+                    Err(e) => issues.push(bad_number(None, e)),
+                }
+            }
+        }
+        None => issues.push(Issue { board: None, line: 1, problem: Problem::NoNumbers }),
+    }
+
+    let mut starts = vec![];
+    let mut first_size = None;
+    // one issue per number never drawn, on the line it is first on
+    let mut undrawn: Vec<Issue> = vec![];
+    let mut undrawn_at: HashMap<u32, usize> = HashMap::new();
+    for block in sections.blocks.iter().filter(|block| !is_pattern(block)) {
+        let board = starts.len();
+        starts.push(block[0].0);
+        let mut on_board = HashSet::new();
+        let mut columns = None;
+        for (i, &(line, text)) in block.iter().enumerate() {
+            let row: Vec<_> = row_numbers(line, text).collect();
+            match columns {
+                Some(expected) if row.len() != expected => {
+                    issues.push(Issue { board: Some(board), line, problem: Problem::Ragged { columns: row.len(), expected } })
+                }
+                _ => columns = columns.or(Some(row.len())),
+            }
+            for (j, number) in row.into_iter().enumerate() {
+                let number = match number {
+                    Ok(number) => number,
+                    Err(e) => {
+                        issues.push(bad_number(Some(board), e));
+                        continue;
+                    }
+                };
+                if !on_board.insert(number) {
+                    issues.push(Issue { board: Some(board), line, problem: Problem::DuplicateNumber { number } });
+                }
+                if !drawn.is_empty() && !drawn.contains(&number) {
+                    let k = *undrawn_at.entry(number).or_insert_with(|| {
+                        undrawn.push(Issue { board: None, line, problem: Problem::NeverDrawn { number, cells: vec![] } });
+                        undrawn.len() - 1
+                    });
+                    if let Problem::NeverDrawn { cells, .. } = &mut undrawn[k].problem {
+                        cells.push((board, (i, j)));
+                    }
+                }
+            }
+        }
+        let size = (block.len(), columns.unwrap_or(0));
+        match first_size {
+            Some((expected_rows, expected_columns)) if size != (expected_rows, expected_columns) => issues.push(Issue {
+                board: Some(board),
+                line: block[0].0,
+                problem: Problem::Dimensions { rows: size.0, columns: size.1, expected_rows, expected_columns },
+            }),
+            _ => first_size = first_size.or(Some(size)),
+        }
+    }
+    issues.extend(undrawn);
+    if starts.is_empty() {
+        issues.push(Issue { board: None, line: s.lines().count().max(1), problem: Problem::NoBoards });
+    }
+
+    if !issues.iter().any(Issue::is_error) {
+        match Bingo::builder(s) {
+            Ok(bingo) => {
+                let bingo = match patterns {
+                    Some(patterns) => bingo.with_patterns(patterns.to_vec()),
+                    None => bingo,
+                };
+                for board in bingo.simulate().never {
+                    issues.push(Issue { board: Some(board), line: starts[board], problem: Problem::NeverWins });
+                }
+            }
+            Err(e) => issues.push(Issue { board: None, line: e.line().unwrap_or(1), problem: Problem::Invalid(e) }),
+        }
+    }
+    issues.sort_by_key(|issue| issue.line);
+    issues
+}
+
+/// The tokenizers only fail on numbers.
+fn bad_number(board: Option<usize>, e: BingoError) -> Issue {
+    match e {
+        BingoError::BadNumber { line, found } => Issue { board, line, problem: Problem::BadNumber { found } },
+        e => Issue { board, line: e.line().unwrap_or(1), problem: Problem::Invalid(e) },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clean_game() {
+        let game = "7,4,9,5\n\n7 4\n9 5\n\n5 9\n4 7";
+        assert_eq!(Vec::<Issue>::new(), lint(game, None));
+    }
+
+    #[test]
+    fn every_problem_with_its_line() {
+        let game = "1,2,3,2,x\n\n1 2\n3 3\n\n1 2 3\n4 5 6\n\n1\n2 9\n\n4 5\n6 7";
+        let issues = lint(game, None);
+        let found: Vec<(Option<usize>, usize, &Problem)> = issues.iter().map(|i| (i.board, i.line, &i.problem)).collect();
+        assert_eq!(
+            vec![
+                (None, 1, &Problem::DuplicateDraw { number: 2 }),
+                (None, 1, &Problem::BadNumber { found: "x".to_string() }),
+                (Some(0), 4, &Problem::DuplicateNumber { number: 3 }),
+                (Some(1), 6, &Problem::Dimensions { rows: 2, columns: 3, expected_rows: 2, expected_columns: 2 }),
+                (None, 7, &Problem::NeverDrawn { number: 4, cells: vec![(1, (1, 0)), (3, (0, 0))] }),
+                (None, 7, &Problem::NeverDrawn { number: 5, cells: vec![(1, (1, 1)), (3, (0, 1))] }),
+                (None, 7, &Problem::NeverDrawn { number: 6, cells: vec![(1, (1, 2)), (3, (1, 0))] }),
+                (Some(2), 9, &Problem::Dimensions { rows: 2, columns: 1, expected_rows: 2, expected_columns: 2 }),
+                (Some(2), 10, &Problem::Ragged { columns: 2, expected: 1 }),
+                (None, 10, &Problem::NeverDrawn { number: 9, cells: vec![(2, (1, 1))] }),
+                (None, 13, &Problem::NeverDrawn { number: 7, cells: vec![(3, (1, 1))] }),
+            ],
+            found
+        );
+        assert_eq!("warning: line 7: 4 is never drawn, on board 2 row 2 column 1, board 4 row 1 column 1", issues[4].to_string());
+        assert_eq!("error: board 2, line 6: the board is 2x3, the first board is 2x2", issues[3].to_string());
+        assert_eq!("warning: line 1: 2 is drawn more than once", issues[0].to_string());
+    }
+
+    #[test]
+    fn boards_that_never_win() {
+        let game = "1,2,3\n\n1 2\n3 4\n\n4 5\n6 7";
+        let issues = lint(game, None);
+        assert!(issues.contains(&Issue { board: Some(1), line: 6, problem: Problem::NeverWins }));
+        assert!(!issues.iter().any(Issue::is_error));
+        // only the cells of a diagonal are needed
+        let issues = lint("1,4\n\n1 2\n3 4", Some(&[WinPattern::Diagonals]));
+        assert_eq!(vec![Problem::NeverDrawn { number: 2, cells: vec![(0, (0, 1))] }, Problem::NeverDrawn { number: 3, cells: vec![(0, (1, 0))] }],
+                   issues.into_iter().map(|issue| issue.problem).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn tall_boards() {
+        assert_eq!(Vec::<Issue>::new(), lint("1,3,5,2,4,6\n\n1 2\n3 4\n5 6", None));
+        let issues = lint("1,3,5\n\n1 2\n3 4\n5 6\n\n2 4\n6 1\n3 5", Some(&[WinPattern::Rows, WinPattern::Diagonals]));
+        assert!(issues.contains(&Issue { board: Some(0), line: 3, problem: Problem::NeverWins }));
+        assert!(!issues.iter().any(Issue::is_error));
+    }
+
+    #[test]
+    fn empty_inputs() {
+        assert_eq!(vec![Issue { board: None, line: 1, problem: Problem::NoNumbers }, Issue { board: None, line: 1, problem: Problem::NoBoards }], lint("", None));
+        let issues = lint("1,2\n\npattern T\n1 2", None);
+        assert_eq!(Problem::NoBoards, issues[0].problem);
+        let issues = lint("1,2,3,4\n\n1 2\n3 4\n\npattern T\n1 2\n1 1", None);
+        assert_eq!(vec![Issue { board: None, line: 7, problem: Problem::Invalid(BingoError::BadPattern { line: 7, problem: "a pattern grid holds only 0 and 1" }) }], issues);
+    }
+}
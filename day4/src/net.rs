@@ -0,0 +1,374 @@
+//! A bingo game over TCP. The server hands each connecting player a board of the
+//! game, draws the numbers and checks the players' claims; the client marks its
+//! board as numbers arrive.
+//!
+//! The protocol is one message per line, boards counted from 0:
+//!
+//! ```text
+//! board 2 5 5 14 21 17 24 4 10 ...     server: your board, rows, columns, numbers
+//! draw 24                              server: a number is drawn
+//! winner 2 4512 rows                   server: a claim was checked, with its score
+//! rejected no bingo on board 0         server: a claim was wrong
+//! full                                 server: every board is taken
+//! over                                 server: every number is drawn
+//! bingo                                client: claims a win
+//! ```
+//!
+//! Players joining late are sent every draw and winner so far. Players only learn
+//! their board, so they can only tell a bingo on rows and columns: games with other
+//! patterns are refused.
+
+use std::fmt::Formatter;
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{Shutdown, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use crate::pattern::WinPattern;
+use crate::{Bingo, Board};
+
+/// How long a send to a player may block the game before the player is dropped.
+const WRITE_TIMEOUT: Duration = Duration::from_secs(2);
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Message {
+    Board { board: usize, rows: usize, columns: usize, numbers: Vec<u32> },
+    Draw { number: u32 },
+    Winner { board: usize, score: u64, pattern: String },
+    Rejected { reason: String },
+    Full,
+    Over,
+    Bingo,
+}
+
+impl Message {
+    pub fn parse(line: &str) -> Option<Message> {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let number = |i: usize| words.get(i)?.parse::<usize>().ok();
+        let message = match *words.first()? {
+            "board" => {
+                let (board, rows, columns) = (number(1)?, number(2)?, number(3)?);
+                let numbers = words[4..].iter().map(|n| n.parse().ok()).collect::<Option<Vec<u32>>>()?;
+                if numbers.len() != rows * columns {
+                    return None;
+                }
+                Message::Board { board, rows, columns, numbers }
+            }
+            "draw" if words.len() == 2 => Message::Draw { number: words[1].parse().ok()? },
+            "winner" if words.len() > 3 => {
+                Message::Winner { board: number(1)?, score: words[2].parse().ok()?, pattern: words[3..].join(" ") }
+            }
+            "rejected" => Message::Rejected { reason: words[1..].join(" ") },
+            "full" => Message::Full,
+            "over" => Message::Over,
+            "bingo" => Message::Bingo,
+            _ => return None,
+        };
+        Some(message)
+    }
+}
+
+impl std::fmt::Display for Message {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Message::Board { board, rows, columns, numbers } => {
+                write!(f, "board {} {} {}", board, rows, columns)?;
+                numbers.iter().try_for_each(|n| write!(f, " {}", n))
+            }
+            Message::Draw { number } => write!(f, "draw {}", number),
+            Message::Winner { board, score, pattern } => write!(f, "winner {} {} {}", board, score, pattern),
+            Message::Rejected { reason } => write!(f, "rejected {}", reason),
+            Message::Full => write!(f, "full"),
+            Message::Over => write!(f, "over"),
+            Message::Bingo => write!(f, "bingo"),
+        }
+    }
+}
+
+fn send(mut stream: &TcpStream, message: &Message) -> io::Result<()> {
+    writeln!(stream, "{}", message)
+}
+
+struct State {
+    bingo: Bingo,
+    /// The game's boards as the server marks them.
+    boards: Vec<Board>,
+    /// The connection of the player holding each board.
+    players: Vec<Option<TcpStream>>,
+    /// Boards handed out so far; a board is never handed out twice.
+    taken: usize,
+    drawn: usize,
+    /// Every draw and winner, for players joining late.
+    history: Vec<Message>,
+    winners: Vec<usize>,
+}
+
+impl State {
+    /// Sends to every player, dropping those whose connection failed or who stopped
+    /// reading for longer than `WRITE_TIMEOUT`.
+    fn broadcast(&mut self, message: Message) {
+        for player in self.players.iter_mut() {
+            if player.as_ref().is_some_and(|stream| send(stream, &message).is_err()) {
+                // a timed out write may have sent part of the line
+                if let Some(stream) = player.take() {
+                    let _ = stream.shutdown(Shutdown::Both);
+                }
+            }
+        }
+        self.history.push(message);
+    }
+
+    fn join(&mut self, stream: TcpStream) -> io::Result<Option<usize>> {
+        // sends happen under the lock, so a player not reading must not stall the game
+        stream.set_write_timeout(Some(WRITE_TIMEOUT))?;
+        if self.taken == self.boards.len() {
+            send(&stream, &Message::Full)?;
+            return Ok(None);
+        }
+        let n = self.taken;
+        let board = &self.bingo.boards()[n];
+        let numbers = (0..board.rows()).flat_map(|i| (0..board.columns()).map(move |j| board.value(i, j))).collect();
+        send(&stream, &Message::Board { board: n, rows: board.rows(), columns: board.columns(), numbers })?;
+        for message in &self.history {
+            send(&stream, message)?;
+        }
+        self.taken += 1;
+        self.players[n] = Some(stream);
+        Ok(Some(n))
+    }
+
+    fn claim(&mut self, n: usize) {
+        let reason = match self.boards[n].completed(&self.bingo.patterns).is_some() {
+            _ if self.winners.contains(&n) => format!("board {} has already won", n),
+            false => format!("no bingo on board {}", n),
+            true => {
+                self.winners.push(n);
+                let board = &self.boards[n];
+                let score = board.score(self.bingo.numbers()[self.drawn - 1]);
+                let pattern = board.completed(&self.bingo.patterns).map_or(String::new(), WinPattern::to_string);
+                self.broadcast(Message::Winner { board: n, score, pattern });
+                return;
+            }
+        };
+        if let Some(stream) = &self.players[n] {
+            let _ = send(stream, &Message::Rejected { reason });
+        }
+    }
+}
+
+/// A game waiting for players.
+pub struct Server {
+    listener: TcpListener,
+    state: Arc<Mutex<State>>,
+}
+
+/// Controls a running server.
+#[derive(Clone)]
+pub struct Handle {
+    state: Arc<Mutex<State>>,
+}
+
+impl Server {
+    /// Fails for games played on anything but rows and columns.
+    pub fn bind(addr: impl ToSocketAddrs, bingo: Bingo) -> io::Result<Server> {
+        let classic = WinPattern::classic();
+        if bingo.patterns.len() != classic.len() || !classic.iter().all(|pattern| bingo.patterns.contains(pattern)) {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "games over the network are played on rows and columns only"));
+        }
+        let mut boards = bingo.boards().to_vec();
+        boards.iter_mut().for_each(Board::reset);
+        let state = State {
+            players: boards.iter().map(|_| None).collect(),
+            boards,
+            bingo,
+            taken: 0,
+            drawn: 0,
+            history: vec![],
+            winners: vec![],
+        };
+        Ok(Server { listener: TcpListener::bind(addr)?, state: Arc::new(Mutex::new(state)) })
+    }
+
+    pub fn local_addr(&self) -> io::Result<std::net::SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    /// Accepts players on a thread of its own, and a thread per player reads claims.
+    pub fn serve(self) -> Handle {
+        let handle = Handle { state: Arc::clone(&self.state) };
+        let state = self.state;
+        thread::spawn(move || {
+            for stream in self.listener.incoming().flatten() {
+                let Ok(reader) = stream.try_clone() else {
+                    continue;
+                };
+                if let Ok(Some(n)) = state.lock().unwrap().join(stream) {
+                    let state = Arc::clone(&state);
+                    thread::spawn(move || read_claims(reader, n, state));
+                }
+            }
+        });
+        handle
+    }
+}
+
+fn read_claims(stream: TcpStream, n: usize, state: Arc<Mutex<State>>) {
+    for line in BufReader::new(stream).lines() {
+        let Ok(line) = line else {
+            break;
+        };
+        if Message::parse(&line) == Some(Message::Bingo) {
+            state.lock().unwrap().claim(n);
+        }
+    }
+    state.lock().unwrap().players[n] = None;
+}
+
+impl Handle {
+    /// Draws and announces the next number. Announces the end of the game once
+    /// every number is drawn, and then returns `None`.
+    pub fn draw(&self) -> Option<u32> {
+        let mut state = self.state.lock().unwrap();
+        let Some(&number) = state.bingo.numbers().get(state.drawn) else {
+            if state.history.last() != Some(&Message::Over) {
+                state.broadcast(Message::Over);
+            }
+            return None;
+        };
+        state.drawn += 1;
+        state.boards.iter_mut().for_each(|board| {
+            board.mark(&number);
+        });
+        state.broadcast(Message::Draw { number });
+        Some(number)
+    }
+
+    /// Draws every `interval` on a thread of its own, until every number is drawn.
+    pub fn draw_every(&self, interval: Duration) -> JoinHandle<()> {
+        let handle = self.clone();
+        thread::spawn(move || loop {
+            thread::sleep(interval);
+            if handle.draw().is_none() {
+                break;
+            }
+        })
+    }
+
+    /// Boards that claimed a win correctly, in the order of their claims.
+    pub fn winners(&self) -> Vec<usize> {
+        self.state.lock().unwrap().winners.clone()
+    }
+
+    /// Every message sent to all players so far.
+    pub fn history(&self) -> Vec<Message> {
+        self.state.lock().unwrap().history.clone()
+    }
+
+    /// Boards handed out so far.
+    pub fn players(&self) -> usize {
+        self.state.lock().unwrap().taken
+    }
+}
+
+/// A player, marking its board as the numbers are drawn.
+pub struct Client {
+    reader: BufReader<TcpStream>,
+    writer: TcpStream,
+    index: usize,
+    board: Board,
+}
+
+impl Client {
+    /// Joins a game and receives a board.
+    pub fn connect(addr: impl ToSocketAddrs) -> io::Result<Client> {
+        let writer = TcpStream::connect(addr)?;
+        let mut reader = BufReader::new(writer.try_clone()?);
+        match read_message(&mut reader)? {
+            Some(Message::Board { board: index, rows, columns, numbers }) => {
+                let lines: Vec<String> = numbers.chunks(columns.max(1)).map(|row| row.iter().map(u32::to_string).collect::<Vec<_>>().join(" ")).collect();
+                let board = Board::builder(&lines.join("\n")).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+                if (board.rows(), board.columns()) != (rows, columns) {
+                    return Err(io::Error::new(io::ErrorKind::InvalidData, "the board does not match its size"));
+                }
+                Ok(Client { reader, writer, index, board })
+            }
+            Some(Message::Full) => Err(io::Error::other("every board is taken")),
+            _ => Err(io::Error::new(io::ErrorKind::InvalidData, "expected a board")),
+        }
+    }
+
+    /// The next message from the server, `None` once it hangs up. Draws are marked
+    /// on the board.
+    pub fn recv(&mut self) -> io::Result<Option<Message>> {
+        let message = read_message(&mut self.reader)?;
+        if let Some(Message::Draw { number }) = &message {
+            self.board.mark(number);
+        }
+        Ok(message)
+    }
+
+    pub fn claim(&self) -> io::Result<()> {
+        send(&self.writer, &Message::Bingo)
+    }
+
+    /// Claims from another thread, while this one waits for messages.
+    pub fn claimer(&self) -> io::Result<Claimer> {
+        Ok(Claimer(self.writer.try_clone()?))
+    }
+
+    /// The board's index in the game.
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    pub fn board(&self) -> &Board {
+        &self.board
+    }
+
+    pub fn set_read_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+        self.writer.set_read_timeout(timeout)
+    }
+}
+
+pub struct Claimer(TcpStream);
+
+impl Claimer {
+    pub fn claim(&self) -> io::Result<()> {
+        send(&self.0, &Message::Bingo)
+    }
+}
+
+fn read_message(reader: &mut impl BufRead) -> io::Result<Option<Message>> {
+    let mut line = String::new();
+    if reader.read_line(&mut line)? == 0 {
+        return Ok(None);
+    }
+    Message::parse(&line)
+        .map(Some)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, format!("unexpected message '{}'", line.trim())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn messages_round_trip() {
+        let messages = [
+            Message::Board { board: 1, rows: 2, columns: 2, numbers: vec![1, 2, 3, 4] },
+            Message::Draw { number: 24 },
+            Message::Winner { board: 2, score: 4512, pattern: "big T".to_string() },
+            Message::Rejected { reason: "no bingo on board 0".to_string() },
+            Message::Full,
+            Message::Over,
+            Message::Bingo,
+        ];
+        for message in messages {
+            assert_eq!(Some(message.clone()), Message::parse(&message.to_string()));
+        }
+        assert_eq!(None, Message::parse("board 0 2 2 1 2 3"));
+        assert_eq!(None, Message::parse("draw"));
+        assert_eq!(None, Message::parse("hello"));
+    }
+}
@@ -0,0 +1,177 @@
+//! How likely each board is to win first or last when the numbers are drawn in a
+//! random order, estimated by simulating many shuffles of the drawn numbers.
+//!
+//! Boards completing on the same draw all count as winning first, or last, so with
+//! ties the probabilities add up to more than one. Last winners are only counted
+//! when every board wins.
+
+use std::thread;
+
+use crate::rng::Rng;
+use crate::Bingo;
+
+/// z for a 95% confidence interval.
+const Z: f64 = 1.96;
+
+/// A value with its 95% confidence interval.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Estimate {
+    pub value: f64,
+    pub low: f64,
+    pub high: f64,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct BoardOdds {
+    pub first: Estimate,
+    pub last: Estimate,
+    /// Numbers drawn until the board wins, over the trials it wins in. `None` when
+    /// it won in fewer than two.
+    pub draws: Option<Estimate>,
+    /// Trials the board won in at all.
+    pub wins: u64,
+}
+
+/// Counts over a share of the trials. Integers, so the total does not depend on
+/// how the trials were split between threads.
+struct Tally {
+    first: Vec<u64>,
+    last: Vec<u64>,
+    wins: Vec<u64>,
+    draws: Vec<u64>,
+    squares: Vec<u64>,
+}
+
+impl Tally {
+    fn new(boards: usize) -> Tally {
+        Tally { first: vec![0; boards], last: vec![0; boards], wins: vec![0; boards], draws: vec![0; boards], squares: vec![0; boards] }
+    }
+
+    fn add(mut self, other: Tally) -> Tally {
+        for (mine, theirs) in [
+            (&mut self.first, other.first),
+            (&mut self.last, other.last),
+            (&mut self.wins, other.wins),
+            (&mut self.draws, other.draws),
+            (&mut self.squares, other.squares),
+        ] {
+            mine.iter_mut().zip(theirs).for_each(|(a, b)| *a += b);
+        }
+        self
+    }
+}
+
+/// Plays `trials` shuffles of the game's numbers on `threads` threads. Trial `t`
+/// shuffles with `Rng::stream(seed, t)`, so a seed always gives the same result.
+pub fn estimate(bingo: &Bingo, trials: u64, seed: u64, threads: usize) -> Vec<BoardOdds> {
+    let boards = bingo.boards().len();
+    let chunk = trials.div_ceil(threads.max(1) as u64).max(1);
+    let tally = thread::scope(|s| {
+        let handles: Vec<_> = (0..trials)
+            .step_by(chunk as usize)
+            .map(|start| s.spawn(move || play_trials(bingo, start..(start + chunk).min(trials), seed)))
+            .collect();
+        handles.into_iter().fold(Tally::new(boards), |total, h| total.add(h.join().unwrap()))
+    });
+
+    (0..boards)
+        .map(|n| BoardOdds {
+            first: proportion(tally.first[n], trials),
+            last: proportion(tally.last[n], trials),
+            draws: mean(tally.draws[n], tally.squares[n], tally.wins[n]),
+            wins: tally.wins[n],
+        })
+        .collect()
+}
+
+fn play_trials(bingo: &Bingo, trials: std::ops::Range<u64>, seed: u64) -> Tally {
+    let mut tally = Tally::new(bingo.boards().len());
+    let mut numbers = bingo.numbers().to_vec();
+    for t in trials {
+        numbers.copy_from_slice(bingo.numbers());
+        Rng::stream(seed, t).shuffle(&mut numbers);
+        let ranking = bingo.simulate_with(&numbers);
+        for finish in &ranking.finishes {
+            let draws = finish.draw as u64 + 1;
+            tally.wins[finish.board] += 1;
+            tally.draws[finish.board] += draws;
+            tally.squares[finish.board] += draws * draws;
+        }
+        if let Some(first) = ranking.finishes.first() {
+            for finish in ranking.finishes.iter().take_while(|finish| finish.draw == first.draw) {
+                tally.first[finish.board] += 1;
+            }
+        }
+        if let (Some(last), true) = (ranking.finishes.last(), ranking.never.is_empty()) {
+            for finish in ranking.finishes.iter().rev().take_while(|finish| finish.draw == last.draw) {
+                tally.last[finish.board] += 1;
+            }
+        }
+    }
+    tally
+}
+
+/// Wilson score interval, which stays inside 0..1 for rare events.
+fn proportion(hits: u64, trials: u64) -> Estimate {
+    if trials == 0 {
+        return Estimate { value: 0.0, low: 0.0, high: 1.0 };
+    }
+    let (n, p) = (trials as f64, hits as f64 / trials as f64);
+    let centre = (p + Z * Z / (2.0 * n)) / (1.0 + Z * Z / n);
+    let spread = Z / (1.0 + Z * Z / n) * (p * (1.0 - p) / n + Z * Z / (4.0 * n * n)).sqrt();
+    Estimate { value: p, low: (centre - spread).max(0.0), high: (centre + spread).min(1.0) }
+}
+
+/// Normal interval of a mean from the sum and the sum of squares of `count` samples.
+fn mean(sum: u64, squares: u64, count: u64) -> Option<Estimate> {
+    if count < 2 {
+        return None;
+    }
+    let n = count as f64;
+    let mean = sum as f64 / n;
+    let variance = ((squares as f64 - n * mean * mean) / (n - 1.0)).max(0.0);
+    let spread = Z * (variance / n).sqrt();
+    Some(Estimate { value: mean, low: mean - spread, high: mean + spread })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn single_cell_boards_are_fair() {
+        // each board wins on its own number, so all orders are equally likely
+        let bingo = Bingo::builder("1,2,3,4\n\n1\n\n2\n\n3\n\n4").unwrap();
+        let odds = estimate(&bingo, 20_000, 1, 4);
+        for board in &odds {
+            assert!(board.first.low < 0.25 && 0.25 < board.first.high, "{:?}", board);
+            assert!(board.last.low < 0.25 && 0.25 < board.last.high, "{:?}", board);
+            let draws = board.draws.unwrap();
+            assert!(draws.low < 2.5 && 2.5 < draws.high, "{:?}", board);
+            assert_eq!(20_000, board.wins);
+        }
+    }
+
+    #[test]
+    fn ties_and_boards_that_never_win() {
+        let bingo = Bingo::builder("1,2\n\n1 2\n\n2 1\n\n3 9").unwrap();
+        let odds = estimate(&bingo, 100, 5, 3);
+        for board in &odds[..2] {
+            assert_eq!(1.0, board.first.value);
+            assert_eq!(Some(1.0), board.draws.map(|draws| draws.value));
+        }
+        // the third board never wins, so nobody is last
+        assert_eq!(0.0, odds[0].last.value);
+        assert_eq!((0.0, 0, None), (odds[2].first.value, odds[2].wins, odds[2].draws));
+    }
+
+    #[test]
+    fn same_seed_same_odds_on_any_number_of_threads() {
+        let bingo = Bingo::builder("5,1,4,2,3,6\n\n1 2\n3 4\n\n4 5\n6 1\n\n2 6\n5 3").unwrap();
+        let odds = estimate(&bingo, 999, 17, 1);
+        assert_eq!(odds, estimate(&bingo, 999, 17, 4));
+        assert_ne!(odds, estimate(&bingo, 999, 18, 4));
+        let total: f64 = odds.iter().map(|board| board.first.value).sum();
+        assert!(total >= 1.0);
+    }
+}
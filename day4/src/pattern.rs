@@ -0,0 +1,228 @@
+//! Which sets of cells complete a bingo card.
+//!
+//! Custom patterns are given in the input file as a block of their own, a
+//! `pattern <name>` line followed by a grid of 0 and 1 the size of the boards:
+//!
+//! ```text
+//! pattern T
+//! 1 1 1
+//! 0 1 0
+//! 0 1 0
+//! ```
+
+use std::fmt::Formatter;
+
+use crate::BingoError;
+
+/// A cell as (row, column).
+pub type Cell = (usize, usize);
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum WinPattern {
+    /// Any full row.
+    Rows,
+    /// Any full column.
+    Columns,
+    /// Either diagonal of a square board.
+    Diagonals,
+    /// The four corners.
+    Corners,
+    /// Every cell.
+    Blackout,
+    /// Both diagonals of a square board.
+    X,
+    /// Every cell set in `mask`, which has the dimensions of the boards.
+    Custom { name: String, mask: Vec<Vec<bool>> },
+}
+
+impl WinPattern {
+    /// Full rows and columns, the puzzle's rule.
+    pub fn classic() -> Vec<WinPattern> {
+        vec![WinPattern::Rows, WinPattern::Columns]
+    }
+
+    /// A built-in pattern, by the name it is displayed with.
+    pub fn by_name(name: &str) -> Option<WinPattern> {
+        match name {
+            "rows" => Some(WinPattern::Rows),
+            "columns" => Some(WinPattern::Columns),
+            "diagonals" => Some(WinPattern::Diagonals),
+            "corners" => Some(WinPattern::Corners),
+            "blackout" => Some(WinPattern::Blackout),
+            "x" => Some(WinPattern::X),
+            _ => None,
+        }
+    }
+
+    /// Reads a `pattern <name>` block, lines numbered as in the input.
+    pub fn parse(lines: &[(usize, &str)]) -> Result<WinPattern, BingoError> {
+        let (line_no, header) = lines[0];
+        let name = match header.trim().strip_prefix("pattern") {
+            Some(name) if !name.trim().is_empty() => name.trim().to_string(),
+            _ => return Err(BingoError::BadPattern { line: line_no, problem: "expected 'pattern <name>'" }),
+        };
+        let mut mask: Vec<Vec<bool>> = vec![];
+        for &(line_no, line) in &lines[1..] {
+            let row = line
+                .chars()
+                .filter(|c| !c.is_whitespace())
+                .map(|c| match c {
+                    '0' => Ok(false),
+                    '1' => Ok(true),
+                    _ => Err(BingoError::BadPattern { line: line_no, problem: "a pattern grid holds only 0 and 1" }),
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            if mask.first().is_some_and(|first| first.len() != row.len()) {
+                return Err(BingoError::BadPattern { line: line_no, problem: "the pattern rows differ in length" });
+            }
+            mask.push(row);
+        }
+        if !mask.iter().flatten().any(|&cell| cell) {
+            return Err(BingoError::BadPattern { line: line_no, problem: "the pattern has no cells set" });
+        }
+        Ok(WinPattern::Custom { name, mask })
+    }
+
+    /// Rows and columns of a custom pattern's grid.
+    pub fn size(&self) -> Option<(usize, usize)> {
+        match self {
+            WinPattern::Custom { mask, .. } => Some((mask.len(), mask[0].len())),
+            _ => None,
+        }
+    }
+
+    /// Every set of cells that completes the pattern on a `rows` x `columns` board.
+    /// Diagonals and X have none on boards that are not square.
+    pub fn lines(&self, rows: usize, columns: usize) -> Vec<Vec<Cell>> {
+        let square = rows == columns;
+        // only built for square boards, where they fit
+        let diagonal = || (0..rows).map(|i| (i, i)).collect::<Vec<_>>();
+        let anti_diagonal = || (0..rows).map(|i| (i, columns - 1 - i)).collect::<Vec<_>>();
+        match self {
+            WinPattern::Rows => (0..rows).map(|i| (0..columns).map(|j| (i, j)).collect()).collect(),
+            WinPattern::Columns => (0..columns).map(|j| (0..rows).map(|i| (i, j)).collect()).collect(),
+            WinPattern::Diagonals if square => vec![diagonal(), anti_diagonal()],
+            WinPattern::X if square => {
+                let mut cells = diagonal();
+                cells.extend(anti_diagonal().into_iter().filter(|&(i, j)| i != j));
+                vec![cells]
+            }
+            WinPattern::Diagonals | WinPattern::X => vec![],
+            WinPattern::Corners => {
+                let mut cells = vec![(0, 0), (0, columns - 1), (rows - 1, 0), (rows - 1, columns - 1)];
+                cells.sort();
+                cells.dedup();
+                vec![cells]
+            }
+            WinPattern::Blackout => vec![(0..rows).flat_map(|i| (0..columns).map(move |j| (i, j))).collect()],
+            WinPattern::Custom { mask, .. } => vec![mask
+                .iter()
+                .enumerate()
+                .flat_map(|(i, row)| row.iter().enumerate().filter(|(_, &set)| set).map(move |(j, _)| (i, j)))
+                .collect()],
+        }
+    }
+}
+
+/// The lines of a game's patterns for one board size, numbered once so that every
+/// board can count hits per line instead of rescanning its cells.
+pub struct LineLayout {
+    columns: usize,
+    /// Pattern index and number of cells of each line.
+    lines: Vec<(usize, usize)>,
+    /// The lines through each cell, cells numbered row by row.
+    by_cell: Vec<Vec<usize>>,
+}
+
+impl LineLayout {
+    pub fn new(patterns: &[WinPattern], rows: usize, columns: usize) -> LineLayout {
+        let mut layout = LineLayout { columns, lines: vec![], by_cell: vec![vec![]; rows * columns] };
+        for (pattern, lines) in patterns.iter().map(|pattern| pattern.lines(rows, columns)).enumerate() {
+            for line in lines {
+                for &(i, j) in &line {
+                    layout.by_cell[i * columns + j].push(layout.lines.len());
+                }
+                layout.lines.push((pattern, line.len()));
+            }
+        }
+        layout
+    }
+}
+
+/// Drawn cells per line of a `LineLayout`, for one board.
+pub struct LineCounter {
+    hits: Vec<usize>,
+}
+
+impl LineCounter {
+    pub fn new(layout: &LineLayout) -> LineCounter {
+        LineCounter { hits: vec![0; layout.lines.len()] }
+    }
+
+    /// Counts a newly drawn cell. Returns the first pattern, by index, that the cell
+    /// completes a line of.
+    pub fn hit(&mut self, layout: &LineLayout, (i, j): Cell) -> Option<usize> {
+        let mut completed = None;
+        for &line in &layout.by_cell[i * layout.columns + j] {
+            self.hits[line] += 1;
+            let (pattern, len) = layout.lines[line];
+            if self.hits[line] == len {
+                completed = Some(completed.map_or(pattern, |c: usize| c.min(pattern)));
+            }
+        }
+        completed
+    }
+}
+
+impl std::fmt::Display for WinPattern {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WinPattern::Rows => write!(f, "rows"),
+            WinPattern::Columns => write!(f, "columns"),
+            WinPattern::Diagonals => write!(f, "diagonals"),
+            WinPattern::Corners => write!(f, "corners"),
+            WinPattern::Blackout => write!(f, "blackout"),
+            WinPattern::X => write!(f, "x"),
+            WinPattern::Custom { name, .. } => write!(f, "{}", name),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn built_in_lines() {
+        assert_eq!(2, WinPattern::Rows.lines(2, 3).len());
+        assert_eq!(vec![(0, 1), (1, 1)], WinPattern::Columns.lines(2, 3)[1]);
+        assert_eq!(vec![vec![(0, 0), (1, 1), (2, 2)], vec![(0, 2), (1, 1), (2, 0)]], WinPattern::Diagonals.lines(3, 3));
+        assert!(WinPattern::Diagonals.lines(2, 3).is_empty());
+        // taller than wide
+        assert_eq!(vec![vec![(0, 0), (0, 1)], vec![(1, 0), (1, 1)], vec![(2, 0), (2, 1)]], WinPattern::Rows.lines(3, 2));
+        assert_eq!(vec![(0, 1), (1, 1), (2, 1)], WinPattern::Columns.lines(3, 2)[1]);
+        assert!(WinPattern::Diagonals.lines(3, 2).is_empty());
+        assert!(WinPattern::X.lines(3, 2).is_empty());
+        assert_eq!(vec![vec![(0, 0), (0, 1), (2, 0), (2, 1)]], WinPattern::Corners.lines(3, 2));
+        assert_eq!(vec![vec![(0, 0), (1, 1), (2, 2), (0, 2), (2, 0)]], WinPattern::X.lines(3, 3));
+        assert_eq!(vec![vec![(0, 0), (0, 2), (1, 0), (1, 2)]], WinPattern::Corners.lines(2, 3));
+        assert_eq!(vec![vec![(0, 0)]], WinPattern::Corners.lines(1, 1));
+        assert_eq!(6, WinPattern::Blackout.lines(2, 3)[0].len());
+        for name in ["rows", "columns", "diagonals", "corners", "blackout", "x"] {
+            assert_eq!(name, WinPattern::by_name(name).unwrap().to_string());
+        }
+    }
+
+    #[test]
+    fn custom_pattern() {
+        let pattern = WinPattern::parse(&[(3, "pattern T"), (4, "1 1 1"), (5, "010"), (6, " 0 1 0")]).unwrap();
+        assert_eq!("T", pattern.to_string());
+        assert_eq!(Some((3, 3)), pattern.size());
+        assert_eq!(vec![vec![(0, 0), (0, 1), (0, 2), (1, 1), (2, 1)]], pattern.lines(3, 3));
+
+        assert_eq!(Err(BingoError::BadPattern { line: 5, problem: "a pattern grid holds only 0 and 1" }),
+                   WinPattern::parse(&[(4, "pattern T"), (5, "1 2")]));
+        assert_eq!(Err(BingoError::BadPattern { line: 4, problem: "the pattern has no cells set" }),
+                   WinPattern::parse(&[(4, "pattern empty"), (5, "0 0")]));
+    }
+}
@@ -0,0 +1,85 @@
+//! A small seedable random number generator, so that random games can be repeated.
+
+/// xorshift64*, seeded through splitmix64 so that nearby seeds give unrelated streams.
+#[derive(Debug, Clone)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Rng {
+        // xorshift never leaves the all-zero state
+        Rng { state: splitmix64(seed).max(1) }
+    }
+
+    /// The `n`th of several independent generators made from one seed.
+    pub fn stream(seed: u64, n: u64) -> Rng {
+        Rng::new(splitmix64(seed).wrapping_add(n))
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        self.state.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+
+    /// A uniform number in `0..n`, `n` above 0.
+    pub fn below(&mut self, n: u64) -> u64 {
+        // drop the top values that would favour the low remainders
+        let zone = u64::MAX - u64::MAX % n;
+        loop {
+            let x = self.next_u64();
+            if x < zone {
+                return x % n;
+            }
+        }
+    }
+
+    /// Fisher-Yates.
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            items.swap(i, self.below(i as u64 + 1) as usize);
+        }
+    }
+}
+
+fn splitmix64(x: u64) -> u64 {
+    let mut z = x.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn repeatable_and_uniform() {
+        let sample = |seed| {
+            let mut rng = Rng::new(seed);
+            (0..5).map(|_| rng.below(1000)).collect::<Vec<_>>()
+        };
+        assert_eq!(sample(7), sample(7));
+        assert_ne!(sample(7), sample(8));
+        assert_ne!(Rng::stream(7, 0).next_u64(), Rng::stream(7, 1).next_u64());
+
+        let mut rng = Rng::new(0);
+        let mut counts = [0; 6];
+        for _ in 0..60_000 {
+            counts[rng.below(6) as usize] += 1;
+        }
+        assert!(counts.iter().all(|&count| (9_500..10_500).contains(&count)), "{:?}", counts);
+    }
+
+    #[test]
+    fn shuffle_permutes() {
+        let mut rng = Rng::new(42);
+        let mut items: Vec<u32> = (0..100).collect();
+        rng.shuffle(&mut items);
+        assert_ne!((0..100).collect::<Vec<_>>(), items);
+        items.sort();
+        assert_eq!((0..100).collect::<Vec<_>>(), items);
+    }
+}
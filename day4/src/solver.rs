@@ -0,0 +1,368 @@
+//! The shortest draw order, taken from the game's drawn numbers, that makes one
+//! board win before all others or after all others.
+//!
+//! Only which numbers are drawn matters, not how often, so orders are searched as
+//! sets of numbers. A board wins first with the numbers of one of its lines when
+//! no other board has a line within them. To win last, every other board needs a
+//! line within the numbers drawn before the final one, while no line of the target
+//! is: each other board picks a line, and the picks are searched depth first with
+//! the union kept as small as the best order found so far.
+
+use std::fmt::Formatter;
+use std::time::{Duration, Instant};
+
+use crate::pattern::Cell;
+use crate::Bingo;
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Goal {
+    WinFirst,
+    LoseLast,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Search {
+    /// The shortest order found, `optimal` unless the time ran out before the
+    /// search finished.
+    Found { draws: Vec<u32>, optimal: bool },
+    /// No order works, with the reason for each line of the target.
+    Impossible { lines: Vec<(Vec<Cell>, Blocked)> },
+    /// The time ran out before any order was found.
+    TimedOut,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Blocked {
+    /// The number is on the line but never drawn.
+    NotDrawn(u32),
+    /// This board has a line within the numbers of the target's line.
+    Beaten(usize),
+    /// The other boards cannot all win without the target winning first.
+    Outrun,
+}
+
+impl std::fmt::Display for Blocked {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Blocked::NotDrawn(number) => write!(f, "{} is never drawn", number),
+            Blocked::Beaten(board) => write!(f, "board {} completes a line with the same numbers", board + 1),
+            Blocked::Outrun => write!(f, "the other boards cannot all win before it"),
+        }
+    }
+}
+
+/// A set of drawn numbers, by their index in the pool of distinct drawn numbers.
+#[derive(Debug, PartialEq, Clone)]
+struct Set(Vec<u64>);
+
+impl Set {
+    fn empty(size: usize) -> Set {
+        Set(vec![0; size.div_ceil(64)])
+    }
+
+    fn insert(&mut self, i: usize) {
+        self.0[i / 64] |= 1 << (i % 64);
+    }
+
+    fn contains(&self, i: usize) -> bool {
+        self.0[i / 64] >> (i % 64) & 1 == 1
+    }
+
+    fn len(&self) -> usize {
+        self.0.iter().map(|word| word.count_ones() as usize).sum()
+    }
+
+    fn union(&self, other: &Set) -> Set {
+        Set(self.0.iter().zip(&other.0).map(|(a, b)| a | b).collect())
+    }
+
+    fn with(&self, i: usize) -> Set {
+        let mut set = self.clone();
+        set.insert(i);
+        set
+    }
+
+    fn without(&self, i: usize) -> Set {
+        let mut set = self.clone();
+        set.0[i / 64] &= !(1 << (i % 64));
+        set
+    }
+
+    fn is_subset(&self, other: &Set) -> bool {
+        self.0.iter().zip(&other.0).all(|(a, b)| a & !b == 0)
+    }
+
+    /// How many numbers `self` adds to `other`.
+    fn missing_from(&self, other: &Set) -> usize {
+        self.0.iter().zip(&other.0).map(|(a, b)| (a & !b).count_ones() as usize).sum()
+    }
+
+    fn members(&self) -> impl Iterator<Item = usize> + '_ {
+        (0..self.0.len() * 64).filter(|&i| self.contains(i))
+    }
+}
+
+/// The cells of each line of a board, with their numbers or a number never drawn.
+type BoardLines = Vec<(Vec<Cell>, Result<Set, u32>)>;
+
+/// The lines of every board as sets of numbers.
+struct Lines {
+    /// Distinct drawn numbers in order of their first draw.
+    pool: Vec<u32>,
+    boards: Vec<BoardLines>,
+}
+
+impl Lines {
+    fn new(bingo: &Bingo) -> Lines {
+        let mut pool: Vec<u32> = vec![];
+        for &number in bingo.numbers() {
+            if !pool.contains(&number) {
+                pool.push(number);
+            }
+        }
+        let boards = bingo
+            .boards()
+            .iter()
+            .map(|board| {
+                bingo
+                    .patterns
+                    .iter()
+                    .flat_map(|pattern| pattern.lines(board.rows(), board.columns()))
+                    .map(|line| {
+                        let mut set = Set::empty(pool.len());
+                        for &(i, j) in &line {
+                            let number = board.value(i, j);
+                            match pool.iter().position(|&drawn| drawn == number) {
+                                Some(index) => set.insert(index),
+                                None => return (line, Err(number)),
+                            }
+                        }
+                        (line, Ok(set))
+                    })
+                    .collect()
+            })
+            .collect();
+        Lines { pool, boards }
+    }
+
+    fn reachable(&self, board: usize) -> impl Iterator<Item = &Set> + '_ {
+        self.boards[board].iter().filter_map(|(_, set)| set.as_ref().ok())
+    }
+
+    /// The numbers of `set` in draw order, with `last` moved to the end.
+    fn draws(&self, set: &Set, last: Option<usize>) -> Vec<u32> {
+        let mut draws: Vec<u32> = set.members().filter(|&i| Some(i) != last).map(|i| self.pool[i]).collect();
+        draws.extend(last.map(|i| self.pool[i]));
+        draws
+    }
+}
+
+/// Searches for at most `limit` for the shortest draw order reaching `goal` for
+/// board `target`.
+pub fn solve(bingo: &Bingo, target: usize, goal: Goal, limit: Duration) -> Search {
+    let lines = Lines::new(bingo);
+    let deadline = Instant::now() + limit;
+    match goal {
+        Goal::WinFirst => win_first(&lines, target),
+        Goal::LoseLast => lose_last(&lines, target, deadline),
+    }
+}
+
+fn win_first(lines: &Lines, target: usize) -> Search {
+    let mut best: Option<&Set> = None;
+    let mut blocked = vec![];
+    for (cells, set) in &lines.boards[target] {
+        let set = match set {
+            Ok(set) => set,
+            Err(number) => {
+                blocked.push((cells.clone(), Blocked::NotDrawn(*number)));
+                continue;
+            }
+        };
+        let beaten = (0..lines.boards.len()).find(|&n| n != target && lines.reachable(n).any(|other| other.is_subset(set)));
+        match beaten {
+            Some(n) => blocked.push((cells.clone(), Blocked::Beaten(n))),
+            None if best.is_none_or(|best| set.len() < best.len()) => best = Some(set),
+            None => {}
+        }
+    }
+    match best {
+        Some(set) => Search::Found { draws: lines.draws(set, None), optimal: true },
+        None => Search::Impossible { lines: blocked },
+    }
+}
+
+/// Depth first search over the lines the other boards win with.
+struct LoseLast<'a> {
+    lines: &'a Lines,
+    target: usize,
+    deadline: Instant,
+    timed_out: bool,
+    /// The smallest set found and the number drawn last.
+    best: Option<(Set, usize)>,
+}
+
+fn lose_last(lines: &Lines, target: usize, deadline: Instant) -> Search {
+    let mut search = LoseLast { lines, target, deadline, timed_out: false, best: None };
+    let mut blocked = vec![];
+    let mut order: Vec<_> = lines.boards[target].iter().collect();
+    order.sort_by_key(|(_, set)| set.as_ref().map_or(usize::MAX, Set::len));
+    for (cells, set) in order {
+        let set = match set {
+            Ok(set) => set,
+            Err(number) => {
+                blocked.push((cells.clone(), Blocked::NotDrawn(*number)));
+                continue;
+            }
+        };
+        // lines come shortest first, so none of the rest can do better
+        if search.timed_out || search.best.as_ref().is_some_and(|(best, _)| set.len() >= best.len()) {
+            break;
+        }
+        if !set.members().fold(false, |found, last| search.start(set, last) | found) {
+            blocked.push((cells.clone(), Blocked::Outrun));
+        }
+    }
+    match (search.best, search.timed_out) {
+        (Some((set, last)), timed_out) => Search::Found { draws: lines.draws(&set, Some(last)), optimal: !timed_out },
+        (None, true) => Search::TimedOut,
+        (None, false) => Search::Impossible { lines: blocked },
+    }
+}
+
+impl LoseLast<'_> {
+    /// Searches with `line` completing the target as `last` is drawn. Tells whether
+    /// any order was found this way.
+    fn start(&mut self, line: &Set, last: usize) -> bool {
+        let before = line.without(last);
+        if self.target_wins(&before) {
+            return false;
+        }
+        // each other board's lines that avoid the last number and do not complete
+        // the target on their own
+        let mut candidates: Vec<Vec<&Set>> = vec![];
+        for n in (0..self.lines.boards.len()).filter(|&n| n != self.target) {
+            let lines: Vec<&Set> = self
+                .lines
+                .reachable(n)
+                .filter(|set| !set.contains(last) && !self.target_wins(&set.union(&before)))
+                .collect();
+            if lines.is_empty() {
+                return false;
+            }
+            candidates.push(lines);
+        }
+        let found = self.best.clone();
+        self.extend(&before, last, &candidates);
+        self.best != found
+    }
+
+    fn target_wins(&self, drawn: &Set) -> bool {
+        self.lines.reachable(self.target).any(|set| set.is_subset(drawn))
+    }
+
+    fn extend(&mut self, before: &Set, last: usize, candidates: &[Vec<&Set>]) {
+        if Instant::now() >= self.deadline {
+            self.timed_out = true;
+        }
+        if self.timed_out {
+            return;
+        }
+        // the boards not won yet, each needing at least its cheapest line
+        let open: Vec<(usize, usize)> = candidates
+            .iter()
+            .enumerate()
+            .filter(|(_, lines)| !lines.iter().any(|set| set.is_subset(before)))
+            .map(|(k, lines)| (k, lines.iter().map(|set| set.missing_from(before)).min().unwrap_or(0)))
+            .collect();
+        let bound = before.len() + 1 + open.iter().map(|&(_, cost)| cost).max().unwrap_or(0);
+        if self.best.as_ref().is_some_and(|(best, _)| bound >= best.len()) {
+            return;
+        }
+        let Some(&(k, _)) = open.iter().min_by_key(|&&(k, _)| candidates[k].len()) else {
+            self.best = Some((before.with(last), last));
+            return;
+        };
+        let mut choices: Vec<Set> = candidates[k].iter().map(|set| set.union(before)).collect();
+        choices.sort_by_key(Set::len);
+        choices.dedup();
+        for drawn in choices {
+            if !self.target_wins(&drawn) {
+                self.extend(&drawn, last, candidates);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::GAME;
+    use crate::pattern::WinPattern;
+    use crate::Strategy;
+
+    /// The game drawing `draws` instead of its own numbers.
+    fn ranking(draws: &[u32]) -> crate::Ranking {
+        Bingo::builder(GAME).unwrap().simulate_with(draws)
+    }
+
+    #[test]
+    fn win_first_with_one_line() {
+        let bingo = Bingo::builder(GAME).unwrap();
+        for target in 0..3 {
+            let Search::Found { draws, optimal: true } = solve(&bingo, target, Goal::WinFirst, Duration::from_secs(5)) else {
+                panic!("board {} can win first", target);
+            };
+            assert_eq!(5, draws.len());
+            let ranking = ranking(&draws);
+            assert_eq!(target, ranking.finishes[0].board);
+            assert!(ranking.finishes.get(1).is_none_or(|second| second.draw > ranking.finishes[0].draw));
+        }
+    }
+
+    #[test]
+    fn lose_last_is_checked_by_simulation() {
+        let bingo = Bingo::builder(GAME).unwrap();
+        for target in 0..3 {
+            let Search::Found { draws, optimal: true } = solve(&bingo, target, Goal::LoseLast, Duration::from_secs(5)) else {
+                panic!("board {} can lose last", target);
+            };
+            let ranking = ranking(&draws);
+            let lose = ranking.query(Strategy::Lose).unwrap();
+            assert_eq!((target, draws.len() - 1), (lose.board, lose.draw));
+            assert!(ranking.finishes[1].draw < lose.draw);
+            // the puzzle's own order is not shorter
+            assert!(draws.len() <= bingo.numbers().len());
+        }
+    }
+
+    #[test]
+    fn lose_last_shares_numbers_between_boards() {
+        // 1 and 2 would complete the target, the other boards both win once 4 and 5
+        // are drawn
+        let bingo = Bingo::builder("1,2,3,4,5\n\n1 2\n3 4\n\n1 2\n5 5\n\n4 5\n1 2").unwrap().with_patterns(vec![WinPattern::Rows]);
+        assert_eq!(Search::Found { draws: vec![4, 5, 3], optimal: true }, solve(&bingo, 0, Goal::LoseLast, Duration::from_secs(5)));
+    }
+
+    #[test]
+    fn impossible_targets() {
+        // the only row of the first board that can be completed is a row of the second,
+        // which cannot win otherwise
+        let bingo = Bingo::builder("1,2,3,4\n\n1 2\n3 9\n\n2 1\n9 8").unwrap().with_patterns(vec![WinPattern::Rows]);
+        assert_eq!(
+            Search::Impossible { lines: vec![(vec![(0, 0), (0, 1)], Blocked::Beaten(1)), (vec![(1, 0), (1, 1)], Blocked::NotDrawn(9))] },
+            solve(&bingo, 0, Goal::WinFirst, Duration::from_secs(5))
+        );
+        assert_eq!(
+            Search::Impossible { lines: vec![(vec![(0, 0), (0, 1)], Blocked::Outrun), (vec![(1, 0), (1, 1)], Blocked::NotDrawn(9))] },
+            solve(&bingo, 0, Goal::LoseLast, Duration::from_secs(5))
+        );
+        assert_eq!("board 2 completes a line with the same numbers", Blocked::Beaten(1).to_string());
+    }
+
+    #[test]
+    fn time_limit() {
+        let bingo = Bingo::builder(GAME).unwrap();
+        assert_eq!(Search::TimedOut, solve(&bingo, 1, Goal::LoseLast, Duration::ZERO));
+    }
+}
@@ -0,0 +1,253 @@
+//! An interactive game in the terminal. Commands, one per line:
+//!
+//! ```text
+//! draw        draw the next number
+//! draw 10     draw the next ten numbers
+//! undo        take back the last draw
+//! goto 20     the game after twenty draws
+//! board 3     show only board 3, `board` alone shows all of them again
+//! score 3     the score of board 3
+//! quit
+//! ```
+//!
+//! Boards are counted from 1. The game itself is a `Game`, so it plays exactly like
+//! `Bingo::simulate`.
+
+use crate::events::Event;
+use crate::game::Game;
+use crate::{Bingo, Board};
+
+const DRAWN: &str = "\x1b[1;7m";
+const WON: &str = "\x1b[1;33m";
+const RESET: &str = "\x1b[0m";
+
+/// Space between boards side by side.
+const GAP: usize = 4;
+
+pub struct Session<'a> {
+    bingo: &'a Bingo,
+    game: Game<'a>,
+    /// The board shown alone, if any.
+    focus: Option<usize>,
+    /// Highlight with ANSI escapes, otherwise drawn numbers are put in brackets.
+    colour: bool,
+    /// Terminal width the boards are laid out in.
+    width: usize,
+}
+
+impl<'a> Session<'a> {
+    pub fn new(bingo: &'a Bingo, colour: bool, width: usize) -> Session<'a> {
+        Session { bingo, game: Game::new(bingo), focus: None, colour, width }
+    }
+
+    pub fn game(&self) -> &Game<'a> {
+        &self.game
+    }
+
+    /// Runs one command and returns what to show. `None` ends the session.
+    pub fn execute(&mut self, command: &str) -> Option<Result<String, &'static str>> {
+        let words: Vec<&str> = command.split_whitespace().collect();
+        let result = match words[..] {
+            ["draw"] => self.draw(1),
+            ["draw", n] => n.parse().map_err(|_| "Expected a number of draws").and_then(|n| self.draw(n)),
+            ["undo"] => match self.game.drawn().len().checked_sub(1) {
+                Some(draws) => Ok(self.goto(draws)),
+                None => Err("Nothing is drawn yet"),
+            },
+            ["goto", n] => n.parse().map_err(|_| "Expected a number of draws").map(|n| self.goto(n)),
+            ["board"] => {
+                self.focus = None;
+                Ok(self.render())
+            }
+            ["board", k] => self.board(k).map(|n| {
+                self.focus = Some(n);
+                self.render()
+            }),
+            ["score", k] => self.board(k).map(|n| self.score(n)),
+            ["quit"] | ["q"] => return None,
+            [] => Ok(self.render()),
+            _ => Err("Expected draw, draw <n>, undo, goto <n>, board <k>, score <k> or quit"),
+        };
+        Some(result)
+    }
+
+    /// A board counted from 1.
+    fn board(&self, k: &str) -> Result<usize, &'static str> {
+        match k.parse::<usize>() {
+            Ok(k) if (1..=self.game.boards().len()).contains(&k) => Ok(k - 1),
+            _ => Err("No such board"),
+        }
+    }
+
+    fn draw(&mut self, n: usize) -> Result<String, &'static str> {
+        if self.game.is_over() {
+            return Err("The game is over");
+        }
+        let mut winners = vec![];
+        for _ in 0..n {
+            let Some(events) = self.game.draw() else {
+                break;
+            };
+            winners.extend(events.iter().filter_map(|event| match event {
+                Event::Won { board, .. } => Some(*board),
+                _ => None,
+            }));
+        }
+        let mut output = self.render();
+        for board in winners {
+            output.push_str(&self.banner(board));
+        }
+        Ok(output)
+    }
+
+    /// Replays the game up to `draws` draws.
+    fn goto(&mut self, draws: usize) -> String {
+        self.game = Game::new(self.bingo);
+        for _ in 0..draws {
+            if self.game.draw().is_none() {
+                break;
+            }
+        }
+        self.render()
+    }
+
+    fn score(&self, n: usize) -> String {
+        let board = &self.game.boards()[n];
+        let current = self.game.last_number().map_or(0, |number| board.score(number));
+        match self.game.won(n) {
+            Some(finish) => format!(
+                "Board {} won on draw {} with {}, score {}, unmarked now {}\n",
+                n + 1, finish.draw + 1, finish.pattern, finish.score, board.unmarked_sum()
+            ),
+            None => format!("Board {} has not won, unmarked {}, score {}\n", n + 1, board.unmarked_sum(), current),
+        }
+    }
+
+    fn banner(&self, n: usize) -> String {
+        let finish = self.game.won(n).expect("a banner is only shown for winners");
+        let text = format!("BINGO! Board {} wins with {}, score {}", n + 1, finish.pattern, finish.score);
+        let rule = "*".repeat(text.len() + 4);
+        match self.colour {
+            true => format!("{WON}{rule}\n* {text} *\n{rule}{RESET}\n"),
+            false => format!("{rule}\n* {text} *\n{rule}\n"),
+        }
+    }
+
+    /// The drawn numbers, then the boards side by side, as many as fit the width.
+    pub fn render(&self) -> String {
+        let drawn = self.game.drawn();
+        let mut output = match drawn.last() {
+            Some(last) => format!("Draw {}: {}\n", drawn.len(), last),
+            None => "Nothing drawn\n".to_string(),
+        };
+        let shown: Vec<usize> = match self.focus {
+            Some(n) => vec![n],
+            None => (0..self.game.boards().len()).collect(),
+        };
+        let blocks: Vec<Vec<(String, usize)>> = shown.iter().map(|&n| self.block(n)).collect();
+        let block_width = blocks.iter().flatten().map(|(_, width)| *width).max().unwrap_or(0);
+        let per_line = ((self.width + GAP) / (block_width + GAP)).max(1);
+        for group in blocks.chunks(per_line) {
+            output.push('\n');
+            for line in 0..group[0].len() {
+                let mut text = String::new();
+                for (k, block) in group.iter().enumerate() {
+                    let (cell, width) = &block[line];
+                    text.push_str(cell);
+                    if k + 1 < group.len() {
+                        text.push_str(&" ".repeat(block_width - width + GAP));
+                    }
+                }
+                output.push_str(text.trim_end());
+                output.push('\n');
+            }
+        }
+        output
+    }
+
+    /// The lines of one board with their width on screen, its title first.
+    fn block(&self, n: usize) -> Vec<(String, usize)> {
+        let board: &Board = &self.game.boards()[n];
+        let title = match self.game.won(n) {
+            Some(finish) => format!("Board {} ({})", n + 1, finish.pattern),
+            None => format!("Board {}", n + 1),
+        };
+        let title_width = title.len();
+        let title = match (self.colour, self.game.won(n).is_some()) {
+            (true, true) => format!("{WON}{title}{RESET}"),
+            _ => title,
+        };
+        let digits = (0..board.rows())
+            .flat_map(|i| (0..board.columns()).map(move |j| board.value(i, j).to_string().len()))
+            .max()
+            .unwrap_or(0);
+        let mut lines = vec![(title, title_width)];
+        for i in 0..board.rows() {
+            let cells: Vec<String> = (0..board.columns())
+                .map(|j| {
+                    let value = board.value(i, j);
+                    match (board.is_drawn(i, j), self.colour) {
+                        (true, true) => format!("{DRAWN} {value:>digits$} {RESET}"),
+                        (true, false) => format!("[{value:>digits$}]"),
+                        (false, _) => format!(" {value:>digits$} "),
+                    }
+                })
+                .collect();
+            lines.push((cells.join(" "), board.columns() * (digits + 3) - 1));
+        }
+        lines
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::GAME;
+
+    fn run(session: &mut Session, command: &str) -> String {
+        session.execute(command).unwrap().unwrap()
+    }
+
+    #[test]
+    fn draw_undo_and_goto() {
+        let bingo = Bingo::builder(GAME).unwrap();
+        let mut session = Session::new(&bingo, false, 80);
+        assert!(run(&mut session, "draw").starts_with("Draw 1: 7\n"));
+        let output = run(&mut session, "draw 11");
+        assert!(output.starts_with("Draw 12: 24\n"));
+        assert!(output.ends_with(&format!("* BINGO! Board 3 wins with rows, score 4512 *\n{}\n", "*".repeat(45))));
+        assert_eq!("Board 3 won on draw 12 with rows, score 4512, unmarked now 188\n", run(&mut session, "score 3"));
+        assert_eq!("Board 1 has not won, unmarked 163, score 3912\n", run(&mut session, "score 1"));
+
+        assert!(run(&mut session, "undo").starts_with("Draw 11: 21\n"));
+        assert_eq!(None, session.game().won(2));
+        assert!(run(&mut session, "goto 0").starts_with("Nothing drawn\n"));
+        assert_eq!(Some(Err("Nothing is drawn yet")), session.execute("undo"));
+        run(&mut session, "goto 100");
+        assert!(session.game().is_over());
+        assert_eq!(Some(Err("The game is over")), session.execute("draw"));
+        assert_eq!(None, session.execute("quit"));
+    }
+
+    #[test]
+    fn layout() {
+        let bingo = Bingo::builder(GAME).unwrap();
+        let mut session = Session::new(&bingo, false, 60);
+        run(&mut session, "draw 3");
+        // two boards of 24 columns fit next to each other in 60
+        let output = session.render();
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!("Board 1                     Board 2", lines[2]);
+        assert_eq!(" 21  [ 9]  14   16  [ 7]     19    8  [ 7]  25   23", lines[5]);
+        assert_eq!("Board 3", lines[9]);
+
+        let output = run(&mut session, "board 2");
+        assert_eq!(vec!["Draw 3: 9", "", "Board 2", "  3   15    0    2   22"], output.lines().take(4).collect::<Vec<_>>());
+        assert_eq!(Some(Err("No such board")), session.execute("board 4"));
+        assert_eq!(Some(Err("Expected a number of draws")), session.execute("draw x"));
+        assert!(session.execute("jump").unwrap().is_err());
+
+        let mut session = Session::new(&bingo, true, 60);
+        assert!(run(&mut session, "draw").contains("\x1b[1;7m  7 \x1b[0m"));
+    }
+}
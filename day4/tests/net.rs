@@ -0,0 +1,107 @@
+//! A server and its players talking over 127.0.0.1.
+
+use std::time::Duration;
+
+use day4::net::{Client, Message, Server};
+use day4::pattern::WinPattern;
+use day4::{Bingo, Strategy};
+
+#[path = "../src/fixtures.rs"]
+mod fixtures;
+
+use fixtures::GAME;
+
+fn join(addr: std::net::SocketAddr) -> Client {
+    let client = Client::connect(addr).unwrap();
+    client.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+    client
+}
+
+/// Reads messages until one that is not a draw.
+fn next_announcement(client: &mut Client) -> Message {
+    loop {
+        match client.recv().unwrap() {
+            Some(Message::Draw { .. }) => continue,
+            Some(message) => return message,
+            None => panic!("the server hung up"),
+        }
+    }
+}
+
+#[test]
+fn players_get_boards_and_claims_are_checked() {
+    let server = Server::bind("127.0.0.1:0", Bingo::builder(GAME).unwrap()).unwrap();
+    let addr = server.local_addr().unwrap();
+    let handle = server.serve();
+
+    let mut players: Vec<Client> = (0..3).map(|_| join(addr)).collect();
+    assert_eq!(vec![0, 1, 2], players.iter().map(Client::index).collect::<Vec<_>>());
+    assert!(matches!(Client::connect(addr), Err(e) if e.to_string() == "every board is taken"));
+    assert_eq!(3, handle.players());
+
+    for _ in 0..12 {
+        handle.draw().unwrap();
+    }
+    for player in players.iter_mut() {
+        for _ in 0..12 {
+            assert!(matches!(player.recv().unwrap(), Some(Message::Draw { .. })));
+        }
+    }
+    assert!(players[2].board().has_bingo());
+    assert!(!players[0].board().has_bingo());
+
+    // a wrong claim is only answered to its player
+    players[0].claim().unwrap();
+    assert_eq!(Message::Rejected { reason: "no bingo on board 0".to_string() }, next_announcement(&mut players[0]));
+
+    players[2].claim().unwrap();
+    let expected = Bingo::builder(GAME).unwrap().play(Strategy::Win).unwrap();
+    let winner = Message::Winner { board: expected.board, score: expected.score, pattern: expected.pattern.to_string() };
+    for player in players.iter_mut() {
+        assert_eq!(winner, next_announcement(player));
+    }
+    players[2].claimer().unwrap().claim().unwrap();
+    assert_eq!(Message::Rejected { reason: "board 2 has already won".to_string() }, next_announcement(&mut players[2]));
+    assert_eq!(vec![2], handle.winners());
+
+    while handle.draw().is_some() {}
+    assert_eq!(Message::Over, next_announcement(&mut players[1]));
+}
+
+#[test]
+fn late_players_catch_up() {
+    let server = Server::bind("127.0.0.1:0", Bingo::builder(GAME).unwrap()).unwrap();
+    let addr = server.local_addr().unwrap();
+    let handle = server.serve();
+    assert_eq!(Some(7), handle.draw());
+    assert_eq!(Some(4), handle.draw());
+
+    let mut late = join(addr);
+    assert_eq!(Some(Message::Draw { number: 7 }), late.recv().unwrap());
+    assert_eq!(Some(Message::Draw { number: 4 }), late.recv().unwrap());
+    // board 0 holds 7 and 4
+    assert!(late.board().is_drawn(2, 4) && late.board().is_drawn(1, 3));
+    assert_eq!(handle.history(), vec![Message::Draw { number: 7 }, Message::Draw { number: 4 }]);
+}
+
+#[test]
+fn draws_on_a_timer() {
+    let server = Server::bind("127.0.0.1:0", Bingo::builder(GAME).unwrap()).unwrap();
+    let addr = server.local_addr().unwrap();
+    let handle = server.serve();
+    let mut player = join(addr);
+    handle.draw_every(Duration::from_millis(1)).join().unwrap();
+    let history = handle.history();
+    assert_eq!(Some(&Message::Over), history.last());
+    assert_eq!(27, history.iter().filter(|message| matches!(message, Message::Draw { .. })).count());
+    assert_eq!(Message::Over, next_announcement(&mut player));
+}
+
+#[test]
+fn only_rows_and_columns_are_served() {
+    let bingo = Bingo::builder(GAME).unwrap().with_patterns(vec![WinPattern::Diagonals]);
+    let err = Server::bind("127.0.0.1:0", bingo).err().unwrap();
+    assert_eq!("games over the network are played on rows and columns only", err.to_string());
+    let bingo = Bingo::builder(GAME).unwrap().with_patterns(vec![WinPattern::Columns, WinPattern::Rows]);
+    assert!(Server::bind("127.0.0.1:0", bingo).is_ok());
+}